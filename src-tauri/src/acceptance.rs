@@ -0,0 +1,343 @@
+// 验收测试脚手架：把需求文档中的验收标准转换为 Gherkin 场景，并生成对应的测试骨架
+use crate::requirement::RequirementItem;
+use crate::testing::{TestFramework, TestRunResult};
+use serde::{Deserialize, Serialize};
+
+/// Gherkin 场景保存的位置（相对于项目根目录）
+pub const FEATURE_FILE: &str = "features/acceptance.feature";
+
+/// 步骤关键字，包括 Gherkin 的中文关键字
+const STEP_KEYWORDS: [&str; 13] = [
+    "Given", "When", "Then", "And", "But", "*", "假如", "假设", "当", "那么", "而且", "并且", "但是",
+];
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Step {
+    pub keyword: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    /// 对应的验收标准，例如 AC-1
+    pub criterion_id: String,
+    pub criterion: String,
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptanceScaffold {
+    pub framework: TestFramework,
+    pub feature_file: String,
+    pub test_file: String,
+    pub scenarios: Vec<Scenario>,
+    /// 本次新增测试骨架的验收标准，已有测试的验收标准不会覆盖
+    pub added: Vec<String>,
+    pub result: TestRunResult,
+}
+
+// ===== 生成场景 =====
+
+/// 条目的第一行，用于标题和注释
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("").trim()
+}
+
+/// 构建把验收标准转换为 Gherkin 场景的提示词
+pub fn build_scenario_prompt(project_name: &str, description: &str, criteria: &[RequirementItem]) -> String {
+    let list = criteria
+        .iter()
+        .map(|c| format!("- {}: {}", c.id, c.text))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "你是 Code Sensei 的编程老师，正在帮助初学者把验收标准写成可以检验的场景。
+
+## 项目
+{}
+
+{}
+
+## 验收标准
+{}
+
+## 任务
+为每条验收标准写一个或多个 Gherkin 场景，描述具体的输入和期望的结果。
+
+## 输出格式
+只在一个代码块中输出 Gherkin，不要有其他说明：
+- 每个场景前一行用标签标注对应的验收标准，例如 `@AC-1`
+- 使用英文关键字 Feature / Scenario / Given / When / Then / And，步骤内容使用中文
+- 不要使用工具创建或修改任何文件",
+        project_name, description, list
+    )
+}
+
+fn parse_step(line: &str) -> Option<Step> {
+    STEP_KEYWORDS.iter().find_map(|keyword| {
+        let rest = line.strip_prefix(keyword)?;
+        // 英文关键字后面必须有空格，中文关键字可以直接接内容
+        if keyword.is_ascii() && !rest.starts_with(' ') {
+            return None;
+        }
+        Some(Step {
+            keyword: keyword.to_string(),
+            text: rest.trim().to_string(),
+        })
+    })
+}
+
+/// 解析 AI 返回的 Gherkin，只保留能对应到验收标准的场景；没有场景的验收标准生成一个默认场景
+pub fn parse_feature(text: &str, criteria: &[RequirementItem]) -> Vec<Scenario> {
+    let find = |id: &str| criteria.iter().find(|c| c.id.eq_ignore_ascii_case(id));
+    let mut scenarios: Vec<Scenario> = Vec::new();
+    let mut tagged: Option<&RequirementItem> = None;
+    let mut in_scenario = false;
+
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('@') {
+            tagged = line
+                .split_whitespace()
+                .find_map(|tag| find(tag.trim_start_matches('@')))
+                .or(tagged);
+        } else if let Some(name) = ["Scenario Outline:", "Scenario:", "场景大纲:", "场景:", "场景："]
+            .iter()
+            .find_map(|k| line.strip_prefix(k))
+        {
+            // 没有标签时从场景名称中找验收标准编号
+            let criterion = tagged.take().or_else(|| {
+                criteria
+                    .iter()
+                    .find(|c| name.to_uppercase().contains(&c.id.to_uppercase()))
+            });
+            in_scenario = criterion.is_some();
+            if let Some(criterion) = criterion {
+                scenarios.push(Scenario {
+                    criterion_id: criterion.id.clone(),
+                    criterion: criterion.text.clone(),
+                    name: name.trim().to_string(),
+                    steps: Vec::new(),
+                });
+            }
+        } else if in_scenario {
+            if let (Some(step), Some(scenario)) = (parse_step(line), scenarios.last_mut()) {
+                scenario.steps.push(step);
+            }
+        }
+    }
+
+    for criterion in criteria {
+        if !scenarios.iter().any(|s| s.criterion_id == criterion.id) {
+            scenarios.push(Scenario {
+                criterion_id: criterion.id.clone(),
+                criterion: criterion.text.clone(),
+                name: first_line(&criterion.text).to_string(),
+                steps: vec![Step {
+                    keyword: "Then".to_string(),
+                    text: first_line(&criterion.text).to_string(),
+                }],
+            });
+        }
+    }
+
+    // 按验收标准的顺序排列
+    let order = |id: &str| criteria.iter().position(|c| c.id == id).unwrap_or(usize::MAX);
+    scenarios.sort_by_key(|s| order(&s.criterion_id));
+    scenarios
+}
+
+fn render_scenario(out: &mut String, scenario: &Scenario) {
+    out.push_str(&format!("\n  @{}\n  Scenario: {}\n", scenario.criterion_id, scenario.name));
+    for step in &scenario.steps {
+        out.push_str(&format!("    {} {}\n", step.keyword, step.text));
+    }
+}
+
+/// 生成 .feature 文件内容
+pub fn render_feature(project_name: &str, scenarios: &[Scenario]) -> String {
+    let mut out = format!("Feature: {} 验收标准\n", project_name);
+    for scenario in scenarios {
+        render_scenario(&mut out, scenario);
+    }
+    out
+}
+
+/// 把场景追加到已有的 .feature 文件，已有 `@AC-n` 标签的验收标准保持不变
+pub fn merge_feature(existing: Option<&str>, project_name: &str, scenarios: &[Scenario]) -> String {
+    let existing = match existing {
+        Some(existing) if !existing.trim().is_empty() => existing,
+        _ => return render_feature(project_name, scenarios),
+    };
+
+    let tagged: Vec<&str> = existing
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('@'))
+        .flat_map(|line| line.split_whitespace().map(|tag| tag.trim_start_matches('@')))
+        .collect();
+
+    let mut out = existing.trim_end().to_string() + "\n";
+    for scenario in scenarios {
+        if !tagged.iter().any(|tag| tag.eq_ignore_ascii_case(&scenario.criterion_id)) {
+            render_scenario(&mut out, scenario);
+        }
+    }
+    out
+}
+
+// ===== 测试骨架 =====
+
+/// 验收测试文件的位置（相对于项目根目录）
+pub fn test_file_path(framework: TestFramework) -> &'static str {
+    match framework {
+        TestFramework::Pytest => "tests/test_acceptance.py",
+        TestFramework::Cargo => "tests/acceptance.rs",
+        TestFramework::Jest => "acceptance.test.js",
+    }
+}
+
+fn file_header(framework: TestFramework) -> &'static str {
+    match framework {
+        TestFramework::Pytest => "\"\"\"验收测试：每个测试对应 requirement.md 中的一条验收标准，实现功能后补全测试内容。\"\"\"\nimport pytest\n",
+        TestFramework::Cargo => "//! 验收测试：每个测试对应 requirement.md 中的一条验收标准，实现功能后补全测试内容。\n",
+        TestFramework::Jest => "// 验收测试：每个测试对应 requirement.md 中的一条验收标准，实现功能后补全测试内容。\n",
+    }
+}
+
+/// 测试中标注验收标准的标记，用于判断测试是否已经存在
+fn marker(criterion_id: &str) -> String {
+    format!("[{}]", criterion_id)
+}
+
+fn function_name(criterion_id: &str) -> String {
+    criterion_id.to_lowercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+fn render_skeleton(framework: TestFramework, scenario: &Scenario) -> String {
+    let id = &scenario.criterion_id;
+    let steps: Vec<String> = scenario
+        .steps
+        .iter()
+        .map(|s| format!("{} {}", s.keyword, s.text))
+        .collect();
+    let criterion = first_line(&scenario.criterion);
+
+    match framework {
+        TestFramework::Pytest => format!(
+            "# {} {}\ndef test_{}():\n    \"\"\"场景：{}\n\n    {}\n    \"\"\"\n    pytest.fail(\"尚未实现：{}\")\n",
+            marker(id),
+            criterion,
+            function_name(id),
+            scenario.name.replace('"', "'"),
+            steps.join("\n    ").replace('"', "'"),
+            id
+        ),
+        TestFramework::Cargo => format!(
+            "// {} {}\n// 场景：{}\n//   {}\n#[test]\nfn {}() {{\n    todo!(\"尚未实现：{}\");\n}}\n",
+            marker(id),
+            criterion,
+            scenario.name,
+            steps.join("\n//   "),
+            function_name(id),
+            id
+        ),
+        TestFramework::Jest => format!(
+            "// {} {}\ntest('{} {}', () => {{\n  // {}\n  throw new Error('尚未实现：{}')\n}})\n",
+            marker(id),
+            criterion,
+            id,
+            scenario.name.replace('\\', "\\\\").replace('\'', "\\'"),
+            steps.join("\n  // "),
+            id
+        ),
+    }
+}
+
+/// 把测试骨架追加到已有的测试文件，已经有测试的验收标准保持不变。返回新内容和新增的验收标准
+pub fn merge_skeletons(existing: Option<&str>, framework: TestFramework, scenarios: &[Scenario]) -> (String, Vec<String>) {
+    let mut content = match existing {
+        Some(existing) if !existing.trim().is_empty() => existing.trim_end().to_string() + "\n",
+        _ => file_header(framework).to_string(),
+    };
+
+    let mut added: Vec<String> = Vec::new();
+    for scenario in scenarios {
+        // 同一验收标准的多个场景合并到一个测试中
+        if content.contains(&marker(&scenario.criterion_id)) {
+            continue;
+        }
+        let steps: Vec<Step> = scenarios
+            .iter()
+            .filter(|s| s.criterion_id == scenario.criterion_id)
+            .flat_map(|s| s.steps.clone())
+            .collect();
+        let merged = Scenario { steps, ..scenario.clone() };
+
+        content.push('\n');
+        if framework != TestFramework::Jest {
+            content.push('\n');
+        }
+        content.push_str(&render_skeleton(framework, &merged));
+        added.push(scenario.criterion_id.clone());
+    }
+    (content, added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn criterion(id: &str, text: &str) -> RequirementItem {
+        serde_json::from_value(serde_json::json!({ "id": id, "text": text, "explicit_id": false })).unwrap()
+    }
+
+    #[test]
+    fn test_parse_feature_links_criteria() {
+        let criteria = vec![criterion("AC-1", "能计算 1+1"), criterion("AC-2", "除数为 0 时提示错误")];
+        let text = "Feature: 计算器\n\n  @AC-1\n  Scenario: 两个数相加\n    Given 打开计算器\n    When 输入 1+1\n    Then 显示 2\n\n  @AC-9\n  Scenario: 无关场景\n    Given 什么都没有\n";
+        let scenarios = parse_feature(text, &criteria);
+
+        assert_eq!(scenarios.len(), 2);
+        assert_eq!(scenarios[0].criterion_id, "AC-1");
+        assert_eq!(scenarios[0].steps.len(), 3);
+        assert_eq!(scenarios[0].steps[1], Step { keyword: "When".to_string(), text: "输入 1+1".to_string() });
+        // 没有场景的验收标准使用默认场景
+        assert_eq!(scenarios[1].criterion_id, "AC-2");
+        assert_eq!(scenarios[1].steps[0].text, "除数为 0 时提示错误");
+        assert!(render_feature("计算器", &scenarios).contains("  @AC-2\n  Scenario: 除数为 0 时提示错误\n"));
+    }
+
+    #[test]
+    fn test_merge_keeps_existing_tests() {
+        let criteria = vec![criterion("AC-1", "能计算 1+1"), criterion("AC-2", "除数为 0 时提示错误")];
+        let scenarios = parse_feature("", &criteria);
+
+        let (first, added) = merge_skeletons(None, TestFramework::Pytest, &scenarios[..1]);
+        assert_eq!(added, vec!["AC-1"]);
+        assert!(first.starts_with("\"\"\"验收测试"));
+        assert!(first.contains("def test_ac_1():"));
+
+        // 学生修改过的测试不会被覆盖
+        let edited = first.replace("pytest.fail(\"尚未实现：AC-1\")", "assert add(1, 1) == 2");
+        let (second, added) = merge_skeletons(Some(&edited), TestFramework::Pytest, &scenarios);
+        assert_eq!(added, vec!["AC-2"]);
+        assert!(second.contains("assert add(1, 1) == 2"));
+        assert!(second.contains("def test_ac_2():"));
+    }
+
+    #[test]
+    fn test_merge_feature_keeps_edited_scenarios() {
+        let criteria = vec![criterion("AC-1", "能计算 1+1"), criterion("AC-2", "除数为 0 时提示错误")];
+        let scenarios = parse_feature("", &criteria);
+
+        let edited = render_feature("计算器", &scenarios[..1]).replace("Then 能计算 1+1", "Then 显示 2");
+        let merged = merge_feature(Some(&edited), "计算器", &scenarios);
+        assert!(merged.starts_with(&edited));
+        assert!(!merged.contains("Then 能计算 1+1"));
+        assert!(merged.ends_with("  @AC-2\n  Scenario: 除数为 0 时提示错误\n    Then 除数为 0 时提示错误\n"));
+    }
+}
@@ -0,0 +1,347 @@
+// 项目导出与导入：把项目元数据、文档、对话、任务和源码打包为单个 zip 文件
+use crate::{ignore, migration, Project};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 归档格式版本
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// 归档清单文件名
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// 项目数据（project.json、requirement.md、chat.json、tasks.json、docs/ 等）在归档中的目录
+const META_DIR: &str = "meta";
+
+/// 源码在归档中的目录
+const SOURCE_DIR: &str = "source";
+
+/// 需求文档在归档中的位置，不论项目是否关联目录都放在 meta/ 下
+const REQUIREMENT_ENTRY: &str = "meta/requirement.md";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub exported_at: i64,
+    pub project: Project,
+    pub includes_source: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+    pub includes_source: bool,
+}
+
+// ===== 导出 =====
+
+struct Exporter {
+    zip: ZipWriter<File>,
+    options: SimpleFileOptions,
+    files: usize,
+    bytes: u64,
+}
+
+impl Exporter {
+    fn add_file(&mut self, name: &str, path: &Path) -> io::Result<()> {
+        let mut content = Vec::new();
+        File::open(path)?.read_to_end(&mut content)?;
+        self.add_bytes(name, &content)
+    }
+
+    fn add_bytes(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        self.zip.start_file(name, self.options)?;
+        self.zip.write_all(content)?;
+        self.files += 1;
+        self.bytes += content.len() as u64;
+        Ok(())
+    }
+
+    /// 递归添加目录，`skip` 判断是否跳过某个条目
+    fn add_dir(
+        &mut self,
+        dir: &Path,
+        base: &Path,
+        prefix: &str,
+        skip: &dyn Fn(&Path, bool) -> bool,
+    ) -> io::Result<()> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+        entries.sort();
+
+        for path in entries {
+            let is_dir = path.is_dir();
+            if skip(&path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                self.add_dir(&path, base, prefix, skip)?;
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(base) else { continue };
+            let name = format!("{}/{}", prefix, relative.to_string_lossy().replace('\\', "/"));
+            self.add_file(&name, &path)?;
+        }
+        Ok(())
+    }
+}
+
+/// 导出项目为 zip 文件
+///
+/// `source_root` 为 None 时不包含源码；源码按文件树相同的规则跳过依赖和构建目录。
+/// `requirement` 为需求文档的实际位置（关联项目在关联目录中），总是写入 meta/requirement.md。
+pub fn export_project(
+    project: &Project,
+    project_dir: &Path,
+    requirement: &Path,
+    source_root: Option<&Path>,
+    target: &Path,
+) -> Result<ExportSummary, String> {
+    let file = File::create(target).map_err(|e| format!("无法创建导出文件: {}", e))?;
+    let mut exporter = Exporter {
+        zip: ZipWriter::new(file),
+        options: SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+        files: 0,
+        bytes: 0,
+    };
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        project: project.clone(),
+        includes_source: source_root.is_some(),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("序列化归档清单失败: {}", e))?;
+    exporter
+        .add_bytes(MANIFEST_NAME, manifest_json.as_bytes())
+        .map_err(|e| format!("写入归档清单失败: {}", e))?;
+
+    // 应用内的 src/ 作为源码单独处理
+    let managed_src = project_dir.join("src");
+    exporter
+        .add_dir(project_dir, project_dir, META_DIR, &|path, _| {
+            path == managed_src || path == requirement
+        })
+        .map_err(|e| format!("打包项目数据失败: {}", e))?;
+    if requirement.is_file() {
+        exporter
+            .add_file(REQUIREMENT_ENTRY, requirement)
+            .map_err(|e| format!("打包需求文档失败: {}", e))?;
+    }
+
+    if let Some(root) = source_root {
+        if root.is_dir() {
+            exporter
+                .add_dir(root, root, SOURCE_DIR, &|path, is_dir| {
+                    path == requirement || ignore::is_ignored(path, is_dir)
+                })
+                .map_err(|e| format!("打包源码失败: {}", e))?;
+        }
+    }
+
+    let files = exporter.files;
+    let bytes = exporter.bytes;
+    exporter
+        .zip
+        .finish()
+        .map_err(|e| format!("完成导出文件失败: {}", e))?;
+
+    Ok(ExportSummary {
+        path: target.display().to_string(),
+        files,
+        bytes,
+        includes_source: source_root.is_some(),
+    })
+}
+
+// ===== 导入 =====
+
+fn open_archive(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| format!("无法打开归档文件: {}", e))?;
+    ZipArchive::new(file).map_err(|e| format!("无效的项目归档: {}", e))
+}
+
+/// 读取归档清单
+pub fn read_manifest(path: &Path) -> Result<ArchiveManifest, String> {
+    let mut archive = open_archive(path)?;
+    let mut entry = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "归档中缺少 manifest.json，不是 Code Sensei 导出的项目".to_string())?;
+
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| format!("读取归档清单失败: {}", e))?;
+
+    let mut manifest: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("解析归档清单失败: {}", e))?;
+    let format_version = manifest["format_version"].as_u64().unwrap_or(0);
+    if format_version > ARCHIVE_FORMAT_VERSION as u64 {
+        return Err(format!(
+            "归档格式版本 {} 过新，请升级 Code Sensei 后再导入",
+            format_version
+        ));
+    }
+
+    // 旧版本导出的项目数据先迁移到当前结构
+    let id = match &manifest["project"]["id"] {
+        serde_json::Value::String(id) => id.clone(),
+        other => other.to_string(),
+    };
+    migration::migrate(&mut manifest["project"], &id)?;
+
+    serde_json::from_value(manifest).map_err(|e| format!("解析归档清单失败: {}", e))
+}
+
+/// 解压归档：项目数据解压到 `project_dir`，源码解压到 `source_dest`，需求文档写到 `requirement_dest`
+///
+/// 条目路径经过 `enclosed_name` 校验，防止写到目标目录之外。
+/// `requirement_dest` 已存在时（关联到本机原有目录）保留原文件，不用归档中的版本覆盖。
+pub fn extract(
+    path: &Path,
+    project_dir: &Path,
+    source_dest: &Path,
+    requirement_dest: &Path,
+) -> Result<usize, String> {
+    let mut archive = open_archive(path)?;
+    let mut count = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("读取归档条目失败: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else { continue };
+
+        let target = if name == Path::new(REQUIREMENT_ENTRY) {
+            if requirement_dest.exists() {
+                continue;
+            }
+            requirement_dest.to_path_buf()
+        } else if let Ok(relative) = name.strip_prefix(META_DIR) {
+            project_dir.join(relative)
+        } else if let Ok(relative) = name.strip_prefix(SOURCE_DIR) {
+            source_dest.join(relative)
+        } else {
+            continue;
+        };
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        let mut out = File::create(&target)
+            .map_err(|e| format!("无法写入 {}: {}", target.display(), e))?;
+        io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("无法写入 {}: {}", target.display(), e))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_and_extract_roundtrip() {
+        let base = std::env::temp_dir().join(format!("code-sensei-archive-{}", std::process::id()));
+        let project_dir = base.join("project");
+        fs::create_dir_all(project_dir.join("src/node_modules")).unwrap();
+        fs::write(project_dir.join("requirement.md"), "# 需求").unwrap();
+        fs::write(project_dir.join("src/main.py"), "print('hi')").unwrap();
+        fs::write(project_dir.join("src/node_modules/dep.js"), "").unwrap();
+
+        let project: Project = serde_json::from_str(
+            r#"{"id":"1","name":"demo","description":"","language":"Python","created_at":0,"updated_at":0}"#,
+        )
+        .unwrap();
+
+        let zip_path = base.join("demo.zip");
+        let requirement = project_dir.join("requirement.md");
+        let summary =
+            export_project(&project, &project_dir, &requirement, Some(&project_dir.join("src")), &zip_path).unwrap();
+        assert_eq!(summary.files, 3);
+        assert_eq!(read_manifest(&zip_path).unwrap().project.name, "demo");
+
+        let imported = base.join("imported");
+        extract(&zip_path, &imported, &imported.join("src"), &imported.join("requirement.md")).unwrap();
+        let requirement = fs::read_to_string(imported.join("requirement.md")).unwrap();
+        let source = fs::read_to_string(imported.join("src/main.py")).unwrap();
+        let skipped = imported.join("src/node_modules").exists();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(requirement, "# 需求");
+        assert_eq!(source, "print('hi')");
+        assert!(!skipped);
+    }
+
+    /// 关联项目的需求文档在关联目录中，导出到 meta/ 后按新的布局放回
+    fn linked_roundtrip(include_source: bool) -> (Option<String>, bool, Option<String>) {
+        let base = std::env::temp_dir().join(format!(
+            "code-sensei-archive-linked-{}-{}",
+            include_source,
+            std::process::id()
+        ));
+        let project_dir = base.join("project");
+        let root = base.join("root");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("requirement.md"), "# 需求").unwrap();
+        fs::write(root.join("main.py"), "print('hi')").unwrap();
+
+        let project: Project = serde_json::from_str(
+            r#"{"id":"1","name":"demo","description":"","language":"Python","created_at":0,"updated_at":0}"#,
+        )
+        .unwrap();
+
+        let zip_path = base.join("demo.zip");
+        let source = if include_source { Some(root.as_path()) } else { None };
+        export_project(&project, &project_dir, &root.join("requirement.md"), source, &zip_path).unwrap();
+
+        // 含源码时导入到新的关联目录，否则导入为应用内管理的项目（需求文档在项目目录中，不在 src/ 中）
+        let imported = base.join("imported");
+        let (src, requirement_dest) = if include_source {
+            let new_root = base.join("new-root");
+            (new_root.clone(), new_root.join("requirement.md"))
+        } else {
+            (imported.join("src"), imported.join("requirement.md"))
+        };
+        extract(&zip_path, &imported, &src, &requirement_dest).unwrap();
+        let requirement = fs::read_to_string(&requirement_dest).ok();
+        let misplaced = if include_source {
+            imported.join("requirement.md").exists()
+        } else {
+            src.join("requirement.md").exists()
+        };
+        let code = fs::read_to_string(src.join("main.py")).ok();
+        fs::remove_dir_all(&base).unwrap();
+
+        (requirement, misplaced, code)
+    }
+
+    #[test]
+    fn test_linked_project_roundtrip_with_source() {
+        let (requirement, misplaced, code) = linked_roundtrip(true);
+        assert_eq!(requirement.as_deref(), Some("# 需求"));
+        assert!(!misplaced);
+        assert_eq!(code.as_deref(), Some("print('hi')"));
+    }
+
+    #[test]
+    fn test_linked_project_roundtrip_without_source() {
+        let (requirement, misplaced, code) = linked_roundtrip(false);
+        assert_eq!(requirement.as_deref(), Some("# 需求"));
+        assert!(!misplaced);
+        assert_eq!(code, None);
+    }
+}
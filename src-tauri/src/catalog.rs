@@ -0,0 +1,168 @@
+// 项目列表查询：按名称、标签、语言和归档状态筛选项目并排序
+use crate::Project;
+use serde::{Deserialize, Serialize};
+
+// ===== 数据结构 =====
+
+/// 归档状态筛选
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFilter {
+    /// 只显示未归档的项目
+    #[default]
+    Active,
+    Archived,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// 按最近活动时间
+    #[default]
+    Activity,
+    Name,
+    Created,
+    Language,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectQuery {
+    /// 在名称和描述中搜索，不区分大小写
+    pub text: Option<String>,
+    /// 必须包含的全部标签
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub archive: ArchiveFilter,
+    pub sort: SortKey,
+    /// 反转排序方向（名称默认升序，时间默认从新到旧）
+    pub reverse: bool,
+}
+
+// ===== 标签 =====
+
+/// 整理标签：去掉首尾空白、空标签和重复标签（不区分大小写）
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !result.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            result.push(tag);
+        }
+    }
+    result
+}
+
+/// 所有项目使用过的标签，按名称排序
+pub fn all_tags(projects: &[Project]) -> Vec<String> {
+    let mut tags = normalize_tags(projects.iter().flat_map(|p| p.tags.clone()).collect());
+    tags.sort_by_key(|t| t.to_lowercase());
+    tags
+}
+
+// ===== 查询 =====
+
+fn matches(project: &Project, query: &ProjectQuery) -> bool {
+    let archive_ok = match query.archive {
+        ArchiveFilter::Active => !project.archived,
+        ArchiveFilter::Archived => project.archived,
+        ArchiveFilter::All => true,
+    };
+    if !archive_ok {
+        return false;
+    }
+
+    if let Some(text) = query.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let text = text.to_lowercase();
+        if !project.name.to_lowercase().contains(&text)
+            && !project.description.to_lowercase().contains(&text)
+        {
+            return false;
+        }
+    }
+
+    if let Some(language) = query.language.as_deref().filter(|l| !l.is_empty()) {
+        let uses_language = project.language.eq_ignore_ascii_case(language)
+            || project.languages.iter().any(|s| s.name.eq_ignore_ascii_case(language));
+        if !uses_language {
+            return false;
+        }
+    }
+
+    query
+        .tags
+        .iter()
+        .all(|tag| project.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+}
+
+/// 筛选并排序项目，置顶的项目始终排在前面
+pub fn query_projects(projects: Vec<Project>, query: &ProjectQuery) -> Vec<Project> {
+    let mut result: Vec<Project> = projects.into_iter().filter(|p| matches(p, query)).collect();
+
+    result.sort_by(|a, b| {
+        let order = match query.sort {
+            SortKey::Activity => b.updated_at.cmp(&a.updated_at),
+            SortKey::Created => b.created_at.cmp(&a.created_at),
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Language => a
+                .language
+                .cmp(&b.language)
+                .then_with(|| b.updated_at.cmp(&a.updated_at)),
+        };
+        let order = if query.reverse { order.reverse() } else { order };
+        b.pinned.cmp(&a.pinned).then(order)
+    });
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, updated_at: i64, tags: &[&str], pinned: bool, archived: bool) -> Project {
+        let mut project: Project = serde_json::from_value(serde_json::json!({
+            "id": name,
+            "name": name,
+            "description": "",
+            "language": "Python",
+            "created_at": 0,
+            "updated_at": updated_at
+        }))
+        .unwrap();
+        project.tags = tags.iter().map(|t| t.to_string()).collect();
+        project.pinned = pinned;
+        project.archived = archived;
+        project
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = normalize_tags(vec![" 作业 ".into(), "".into(), "Loop".into(), "loop".into()]);
+        assert_eq!(tags, vec!["作业", "Loop"]);
+    }
+
+    #[test]
+    fn test_query_projects() {
+        let projects = vec![
+            project("old", 1, &["作业"], false, false),
+            project("new", 3, &["作业"], false, false),
+            project("pinned", 0, &[], true, false),
+            project("archived", 5, &["作业"], false, true),
+        ];
+
+        let all = query_projects(projects.clone(), &ProjectQuery::default());
+        let names: Vec<_> = all.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["pinned", "new", "old"]);
+
+        let query = ProjectQuery {
+            tags: vec!["作业".into()],
+            archive: ArchiveFilter::All,
+            sort: SortKey::Name,
+            ..Default::default()
+        };
+        let names: Vec<_> = query_projects(projects, &query).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["archived", "new", "old"]);
+    }
+}
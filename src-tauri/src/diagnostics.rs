@@ -0,0 +1,455 @@
+// 运行错误与编译诊断解析：从输出中提取出错的文件和行号
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 代码片段中出错行前后保留的行数
+const SNIPPET_CONTEXT: u32 = 3;
+
+/// 解释时最多附带的出错位置数
+const MAX_LOCATIONS: usize = 5;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorLocation {
+    /// 相对于项目根目录的路径（解析阶段可能是原始路径）
+    pub file: String,
+    pub line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// 该位置对应的诊断信息（编译器可能一次报告多个错误）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedError {
+    /// "python" / "rust" / "node" / "unknown"
+    pub kind: String,
+    /// 错误摘要，例如 `ZeroDivisionError: division by zero`
+    pub message: String,
+    pub locations: Vec<ErrorLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorExplanation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub kind: String,
+    pub message: String,
+    pub locations: Vec<ErrorLocation>,
+    pub explanation: String,
+}
+
+impl ParsedError {
+    fn new(kind: &str, message: String, locations: Vec<ErrorLocation>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message,
+            locations,
+        }
+    }
+}
+
+fn location(file: &str, line: u32, column: Option<u32>, message: Option<String>) -> ErrorLocation {
+    ErrorLocation {
+        file: file.to_string(),
+        line,
+        column,
+        message,
+        snippet: None,
+    }
+}
+
+// ===== 解析器 =====
+
+/// 拆分 `path:line[:column]`，兼容 Windows 盘符
+fn split_location(text: &str) -> Option<(String, u32, Option<u32>)> {
+    let text = text.trim().trim_end_matches(':');
+    let text = text.strip_prefix("file://").unwrap_or(text);
+
+    let mut parts = text.rsplitn(3, ':');
+    let last = parts.next()?;
+    let middle = parts.next()?;
+
+    match (middle.parse::<u32>(), last.parse::<u32>(), parts.next()) {
+        (Ok(line), Ok(column), Some(path)) if !path.is_empty() => {
+            Some((path.to_string(), line, Some(column)))
+        }
+        _ => {
+            let line = last.parse::<u32>().ok()?;
+            let path = text[..text.len() - last.len() - 1].to_string();
+            if path.is_empty() {
+                return None;
+            }
+            Some((path, line, None))
+        }
+    }
+}
+
+/// 解析 Python traceback
+pub fn parse_python_traceback(text: &str) -> Option<ParsedError> {
+    let mut locations = Vec::new();
+    let mut message = String::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("File \"") {
+            let Some(end) = rest.find('"') else { continue };
+            let file = &rest[..end];
+            let after = &rest[end + 1..];
+            let Some(line_part) = after.strip_prefix(", line ") else { continue };
+            let digits: String = line_part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(line_no) = digits.parse::<u32>() {
+                locations.push(location(file, line_no, None, None));
+            }
+        } else if !line.starts_with(char::is_whitespace)
+            && !trimmed.is_empty()
+            && !trimmed.starts_with("Traceback")
+            && !trimmed.starts_with("During handling")
+            && !trimmed.starts_with("The above exception")
+        {
+            message = trimmed.to_string();
+        }
+    }
+
+    if locations.is_empty() {
+        return None;
+    }
+
+    Some(ParsedError::new("python", message, locations))
+}
+
+/// 解析 rustc / cargo 的 JSON 诊断（`--message-format=json` 或 `--error-format=json`）
+pub fn parse_rust_json(text: &str) -> Option<ParsedError> {
+    let mut locations = Vec::new();
+    let mut messages = Vec::new();
+
+    for line in text.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line.trim()) else { continue };
+
+        let diagnostic = if value["reason"] == "compiler-message" {
+            &value["message"]
+        } else if value["spans"].is_array() {
+            &value
+        } else {
+            continue;
+        };
+
+        if diagnostic["level"] != "error" {
+            continue;
+        }
+
+        let summary = diagnostic["message"].as_str().unwrap_or("").to_string();
+        let code = diagnostic["code"]["code"].as_str();
+        let summary = match code {
+            Some(code) => format!("error[{}]: {}", code, summary),
+            None => format!("error: {}", summary),
+        };
+
+        if let Some(spans) = diagnostic["spans"].as_array() {
+            for span in spans.iter().filter(|s| s["is_primary"] == true) {
+                let Some(file) = span["file_name"].as_str() else { continue };
+                let line_no = span["line_start"].as_u64().unwrap_or(0) as u32;
+                let column = span["column_start"].as_u64().map(|c| c as u32);
+                locations.push(location(file, line_no, column, Some(summary.clone())));
+            }
+        }
+        messages.push(summary);
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    Some(ParsedError::new("rust", messages.join("\n"), locations))
+}
+
+/// 解析 rustc 的文本诊断和运行时 panic 信息
+pub fn parse_rust_human(text: &str) -> Option<ParsedError> {
+    let mut locations = Vec::new();
+    let mut messages = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("error[") || (trimmed.starts_with("error: ") && !trimmed.starts_with("error: could not compile")) {
+            current = Some(trimmed.to_string());
+            messages.push(trimmed.to_string());
+        } else if let Some(rest) = trimmed.strip_prefix("--> ") {
+            if let (Some(summary), Some((file, line_no, column))) = (current.take(), split_location(rest)) {
+                locations.push(location(&file, line_no, column, Some(summary)));
+            }
+        } else if let Some(pos) = trimmed.find("panicked at ") {
+            let rest = &trimmed[pos + "panicked at ".len()..];
+            // 旧格式: panicked at 'msg', src/main.rs:5:9
+            let rest = match rest.strip_prefix('\'') {
+                Some(quoted) => quoted.rsplit_once("', ").map(|(_, loc)| loc).unwrap_or(quoted),
+                None => rest,
+            };
+            if let Some((file, line_no, column)) = split_location(rest) {
+                locations.push(location(&file, line_no, column, None));
+            }
+            messages.push(trimmed.to_string());
+        }
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    Some(ParsedError::new("rust", messages.join("\n"), locations))
+}
+
+/// 解析 Node.js 的异常和调用栈
+pub fn parse_node_stack(text: &str) -> Option<ParsedError> {
+    let mut locations = Vec::new();
+    let mut message = String::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some(frame) = trimmed.strip_prefix("at ") {
+            // at fn (/path/file.js:10:5) 或 at /path/file.js:10:5
+            let target = match (frame.rfind('('), frame.ends_with(')')) {
+                (Some(start), true) => &frame[start + 1..frame.len() - 1],
+                _ => frame,
+            };
+            if target.starts_with("node:") || target == "<anonymous>" {
+                continue;
+            }
+            if let Some((file, line_no, column)) = split_location(target) {
+                locations.push(location(&file, line_no, column, None));
+            }
+        } else if message.is_empty()
+            && !line.starts_with(char::is_whitespace)
+            && (trimmed.contains("Error:") || trimmed.starts_with("Error"))
+        {
+            message = trimmed.to_string();
+        }
+    }
+
+    if locations.is_empty() {
+        return None;
+    }
+
+    Some(ParsedError::new("node", message, locations))
+}
+
+/// 选择用于解析的输出：错误信息在标准错误中，标准错误为空时才使用标准输出
+pub fn error_output<'a>(stderr: &'a str, stdout: &'a str) -> &'a str {
+    if stderr.trim().is_empty() {
+        stdout
+    } else {
+        stderr
+    }
+}
+
+/// 依次尝试各种解析器
+pub fn parse_error_output(text: &str) -> Option<ParsedError> {
+    parse_rust_json(text)
+        .or_else(|| parse_python_traceback(text))
+        .or_else(|| parse_node_stack(text))
+        .or_else(|| parse_rust_human(text))
+}
+
+/// 未能识别时，以最后一行非空输出作为错误摘要
+pub fn fallback_error(text: &str) -> ParsedError {
+    let message = text
+        .lines()
+        .rev()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())
+        .unwrap_or("")
+        .to_string();
+    ParsedError::new("unknown", message, Vec::new())
+}
+
+// ===== 映射到项目文件 =====
+
+/// 将诊断中的路径映射为项目内的相对路径，项目外的文件（标准库、依赖）返回 None
+pub fn map_to_project(root: &Path, raw: &str) -> Option<String> {
+    let raw = raw.strip_prefix("file://").unwrap_or(raw);
+    let path = Path::new(raw);
+
+    let relative: PathBuf = if path.is_absolute() {
+        match path.strip_prefix(root) {
+            Ok(p) => p.to_path_buf(),
+            Err(_) => {
+                let canonical_root = root.canonicalize().ok()?;
+                let canonical = path.canonicalize().ok()?;
+                canonical.strip_prefix(&canonical_root).ok()?.to_path_buf()
+            }
+        }
+    } else {
+        path.to_path_buf()
+    };
+
+    if !root.join(&relative).is_file() {
+        return None;
+    }
+
+    let relative = relative.to_str()?.replace('\\', "/");
+    Some(relative.trim_start_matches("./").to_string())
+}
+
+/// 读取出错行附近的代码片段，出错行以 `>` 标记
+pub fn read_snippet(root: &Path, relative: &str, line: u32) -> Option<String> {
+    let content = fs::read_to_string(root.join(relative)).ok()?;
+    let start = line.saturating_sub(SNIPPET_CONTEXT).max(1);
+    let end = line + SNIPPET_CONTEXT;
+
+    let snippet = content
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i as u32 + 1, text))
+        .filter(|(no, _)| *no >= start && *no <= end)
+        .map(|(no, text)| {
+            let marker = if no == line { ">" } else { " " };
+            format!("{} {:>4} | {}", marker, no, text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if snippet.is_empty() { None } else { Some(snippet) }
+}
+
+/// 把解析出的位置映射到项目文件树中并附带代码片段，项目外的位置会被丢弃
+pub fn resolve_locations(parsed: &mut ParsedError, root: &Path) {
+    let mut resolved: Vec<ErrorLocation> = Vec::new();
+
+    for loc in parsed.locations.drain(..) {
+        let Some(file) = map_to_project(root, &loc.file) else { continue };
+        if resolved.iter().any(|r| r.file == file && r.line == loc.line) {
+            continue;
+        }
+        let snippet = read_snippet(root, &file, loc.line);
+        resolved.push(ErrorLocation { file, snippet, ..loc });
+    }
+
+    // Python 的最后一帧最接近出错位置，优先展示
+    if parsed.kind == "python" {
+        resolved.reverse();
+    }
+    resolved.truncate(MAX_LOCATIONS);
+    parsed.locations = resolved;
+}
+
+// ===== 提示词 =====
+
+/// 构建错误解释提示词
+pub fn build_explain_prompt(parsed: &ParsedError, output: &str, language: &str) -> String {
+    let locations = if parsed.locations.is_empty() {
+        "（未能定位到项目中的文件）".to_string()
+    } else {
+        parsed
+            .locations
+            .iter()
+            .map(|loc| {
+                format!(
+                    "### {}:{}\n{}\n```\n{}\n```",
+                    loc.file,
+                    loc.line,
+                    loc.message.clone().unwrap_or_default(),
+                    loc.snippet.clone().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    };
+
+    // 只保留输出末尾，错误信息通常在最后
+    let tail: Vec<&str> = output.lines().rev().take(60).collect();
+    let tail = tail.into_iter().rev().collect::<Vec<_>>().join("\n");
+
+    format!(
+        "你是 Code Sensei 的编程老师，正在帮助一位初学者理解程序运行失败的原因。
+
+## 编程语言
+{}
+
+## 错误摘要
+{}
+
+## 出错位置
+{}
+
+## 程序输出（末尾部分）
+```
+{}
+```
+
+## 任务
+用通俗易懂的中文向初学者解释：
+1. 出了什么错，错误信息是什么意思
+2. 为什么会出错（结合上面的代码片段）
+3. 应该如何修改，可以给出修改示例
+
+## 要求
+- 不要修改、创建或删除任何文件，只做解释
+- 避免堆砌术语，必要的术语请顺带解释
+- 直接输出解释内容",
+        language, parsed.message, locations, tail
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_python_traceback() {
+        let output = "Traceback (most recent call last):
+  File \"/home/u/proj/main.py\", line 10, in <module>
+    foo()
+  File \"/home/u/proj/util.py\", line 3, in foo
+    return 1 / 0
+ZeroDivisionError: division by zero";
+
+        let parsed = parse_python_traceback(output).unwrap();
+        assert_eq!(parsed.message, "ZeroDivisionError: division by zero");
+        assert_eq!(parsed.locations.len(), 2);
+        assert_eq!(parsed.locations[1].file, "/home/u/proj/util.py");
+        assert_eq!(parsed.locations[1].line, 3);
+    }
+
+    #[test]
+    fn test_error_output_ignores_stdout_when_stderr_present() {
+        let stderr = "Traceback (most recent call last):\n  File \"main.py\", line 2, in <module>\n    1 / 0\nZeroDivisionError: division by zero\n";
+        let parsed = parse_error_output(error_output(stderr, "hi\n")).unwrap();
+        assert_eq!(parsed.message, "ZeroDivisionError: division by zero");
+        assert_eq!(error_output("  \n", "panic"), "panic");
+    }
+
+    #[test]
+    fn test_parse_rust_json() {
+        let output = r#"{"reason":"compiler-artifact","package_id":"x"}
+{"reason":"compiler-message","message":{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"src/main.rs","line_start":4,"column_start":18,"is_primary":true}]}}"#;
+
+        let parsed = parse_rust_json(output).unwrap();
+        assert_eq!(parsed.message, "error[E0308]: mismatched types");
+        assert_eq!(parsed.locations[0].file, "src/main.rs");
+        assert_eq!(parsed.locations[0].column, Some(18));
+    }
+
+    #[test]
+    fn test_parse_node_stack() {
+        let output = "/home/u/proj/index.js:5
+    foo.bar();
+        ^
+
+TypeError: Cannot read properties of undefined (reading 'bar')
+    at Object.<anonymous> (/home/u/proj/index.js:5:9)
+    at Module._compile (node:internal/modules/cjs/loader:1256:14)";
+
+        let parsed = parse_node_stack(output).unwrap();
+        assert!(parsed.message.starts_with("TypeError"));
+        assert_eq!(parsed.locations.len(), 1);
+        assert_eq!(parsed.locations[0].line, 5);
+        assert_eq!(parsed.locations[0].column, Some(9));
+    }
+}
@@ -0,0 +1,268 @@
+// 项目文档：根据代码生成架构概览、模块参考和 README，记录生成时使用的源文件以判断文档是否过期
+use crate::onboarding;
+use crate::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 生成文档时使用的源文件记录（位于项目目录）
+const SOURCES_FILE: &str = "doc_sources.json";
+
+/// 文档保存的目录（位于项目目录）
+pub const DOCS_DIR: &str = "docs";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DocKind {
+    Architecture,
+    Reference,
+    Readme,
+}
+
+pub const DOC_KINDS: [DocKind; 3] = [DocKind::Architecture, DocKind::Reference, DocKind::Readme];
+
+impl DocKind {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            DocKind::Architecture => onboarding::OVERVIEW_FILE,
+            DocKind::Reference => "reference.md",
+            DocKind::Readme => "README.md",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DocKind::Architecture => "架构概览",
+            DocKind::Reference => "模块参考",
+            DocKind::Readme => "README",
+        }
+    }
+
+    fn task(self) -> &'static str {
+        match self {
+            DocKind::Architecture => "写一份面向初学者的架构概览，包含：
+- 项目是做什么的
+- 目录结构和主要模块的职责
+- 程序从哪里开始运行，主要的数据和调用流程
+- 使用的框架和依赖
+- 建议先阅读的文件",
+            DocKind::Reference => "写一份模块和 API 参考，按文件分节，包含：
+- 每个模块的职责
+- 公开的函数、类和方法：参数、返回值和用途
+- 需要注意的错误处理和边界情况",
+            DocKind::Readme => "写一份 README，包含：
+- 项目名称和一句话介绍
+- 主要功能
+- 环境要求、安装和运行方法
+- 如何运行测试
+- 目录结构简介",
+        }
+    }
+}
+
+/// 源文件的内容指纹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceStamp {
+    pub path: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocRecord {
+    pub generated_at: i64,
+    pub sources: Vec<SourceStamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocStatus {
+    pub kind: DocKind,
+    /// 相对于项目目录的路径
+    pub file: String,
+    pub exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<i64>,
+    /// 源文件在生成后发生了变化，或者文档不是由这里生成的
+    pub stale: bool,
+    pub changed: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedDoc {
+    pub status: DocStatus,
+    pub content: String,
+}
+
+// ===== 源文件记录 =====
+
+/// FNV-1a 哈希，结果在不同平台和编译器版本间保持一致
+pub fn fingerprint(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// 文档依赖的源文件：全部代码文件和项目清单文件
+pub fn source_files(root: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    trace::collect_code_files(root, root, &mut files);
+    if let Some(report) = crate::language::detect_languages(root) {
+        files.extend(report.manifests);
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+pub fn stamp_sources(root: &Path, files: &[String]) -> Vec<SourceStamp> {
+    files
+        .iter()
+        .filter_map(|path| {
+            fs::read(root.join(path)).ok().map(|bytes| SourceStamp {
+                path: path.clone(),
+                hash: fingerprint(&bytes),
+            })
+        })
+        .collect()
+}
+
+fn load_records(project_dir: &Path) -> HashMap<DocKind, DocRecord> {
+    fs::read_to_string(project_dir.join(SOURCES_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 保存文档并记录生成时使用的源文件
+pub fn save_doc(project_dir: &Path, root: &Path, kind: DocKind, content: &str) -> Result<(), String> {
+    let docs_dir = project_dir.join(DOCS_DIR);
+    fs::create_dir_all(&docs_dir).map_err(|e| format!("无法创建 docs 目录: {}", e))?;
+    fs::write(docs_dir.join(kind.file_name()), content).map_err(|e| format!("无法保存{}: {}", kind.name(), e))?;
+
+    let mut records = load_records(project_dir);
+    records.insert(
+        kind,
+        DocRecord {
+            generated_at: chrono::Utc::now().timestamp(),
+            sources: stamp_sources(root, &source_files(root)),
+        },
+    );
+    let json = serde_json::to_string_pretty(&records).map_err(|e| format!("无法序列化文档记录: {}", e))?;
+    fs::write(project_dir.join(SOURCES_FILE), json).map_err(|e| format!("无法保存文档记录: {}", e))
+}
+
+/// 对比生成时的源文件和当前的源文件
+pub fn doc_status(project_dir: &Path, root: &Path, kind: DocKind) -> DocStatus {
+    let file = format!("{}/{}", DOCS_DIR, kind.file_name());
+    let exists = project_dir.join(&file).is_file();
+    let record = load_records(project_dir).remove(&kind);
+
+    let mut status = DocStatus {
+        kind,
+        file,
+        exists,
+        generated_at: record.as_ref().map(|r| r.generated_at),
+        stale: true,
+        changed: Vec::new(),
+        added: Vec::new(),
+        removed: Vec::new(),
+    };
+    let Some(record) = record.filter(|_| exists) else { return status };
+
+    let current = stamp_sources(root, &source_files(root));
+    for stamp in &current {
+        match record.sources.iter().find(|s| s.path == stamp.path) {
+            Some(old) if old.hash != stamp.hash => status.changed.push(stamp.path.clone()),
+            Some(_) => {}
+            None => status.added.push(stamp.path.clone()),
+        }
+    }
+    status.removed = record
+        .sources
+        .iter()
+        .filter(|old| !current.iter().any(|s| s.path == old.path))
+        .map(|old| old.path.clone())
+        .collect();
+    status.stale = !(status.changed.is_empty() && status.added.is_empty() && status.removed.is_empty());
+    status
+}
+
+pub fn list_status(project_dir: &Path, root: &Path) -> Vec<DocStatus> {
+    DOC_KINDS.iter().map(|kind| doc_status(project_dir, root, *kind)).collect()
+}
+
+// ===== 提示词 =====
+
+/// 构建生成文档的提示词，`previous` 为已有的文档内容，刷新时在其基础上修改
+pub fn build_prompt(kind: DocKind, project_name: &str, root: &str, digest: &str, previous: Option<&str>) -> String {
+    let previous = previous
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| format!("## 现有文档\n代码已经修改，请在现有文档的基础上更新，保留仍然正确的内容：\n\n{}\n\n", p.trim_end()))
+        .unwrap_or_default();
+
+    format!(
+        "你是 Code Sensei 的编程老师，正在为项目「{}」编写文档。
+
+## 项目路径
+{}
+
+## 代码摘要
+{}
+
+{}## 任务
+可以用 Read 工具阅读项目中的其他文件，然后用中文{}
+
+## 要求
+- 只描述代码中实际存在的内容，不要编造
+- 不要修改、创建或删除任何文件
+- 使用 Markdown 格式，以一级标题开头
+- 直接输出文档内容，不要有其他说明",
+        project_name,
+        root,
+        digest,
+        previous,
+        kind.task()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_status_detects_changes() {
+        let base = std::env::temp_dir().join(format!("code-sensei-docs-{}", std::process::id()));
+        let project_dir = base.join("project");
+        let root = base.join("root");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.py"), "print(1)\n").unwrap();
+        fs::write(root.join("util.py"), "x = 1\n").unwrap();
+
+        let missing = doc_status(&project_dir, &root, DocKind::Reference);
+        save_doc(&project_dir, &root, DocKind::Reference, "# 参考\n").unwrap();
+        let fresh = doc_status(&project_dir, &root, DocKind::Reference);
+
+        fs::write(root.join("main.py"), "print(2)\n").unwrap();
+        fs::remove_file(root.join("util.py")).unwrap();
+        fs::write(root.join("new.py"), "").unwrap();
+        let stale = doc_status(&project_dir, &root, DocKind::Reference);
+        let readme = doc_status(&project_dir, &root, DocKind::Readme);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(!missing.exists && missing.stale);
+        assert!(fresh.exists && !fresh.stale);
+        assert!(stale.stale);
+        assert_eq!(stale.changed, vec!["main.py"]);
+        assert_eq!(stale.added, vec!["new.py"]);
+        assert_eq!(stale.removed, vec!["util.py"]);
+        assert!(!readme.exists);
+    }
+}
@@ -0,0 +1,92 @@
+// 复制项目：把项目数据和源码复制为一个新项目，并记录来源项目
+use crate::{ignore, relink};
+use std::fs;
+use std::path::Path;
+
+/// 重置历史时不复制的文件
+const HISTORY_FILES: [&str; 2] = ["chat.json", "tasks.json"];
+
+/// 目录不存在或为空
+pub fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true)
+}
+
+/// 递归复制目录，`skip` 返回 true 的条目不复制，返回复制的文件数
+pub fn copy_tree(source: &Path, target: &Path, skip: &dyn Fn(&Path, bool) -> bool) -> Result<usize, String> {
+    fs::create_dir_all(target).map_err(|e| format!("无法创建目录: {}", e))?;
+    let entries = fs::read_dir(source).map_err(|e| format!("无法读取目录 {}: {}", source.display(), e))?;
+
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if skip(&path, is_dir) {
+            continue;
+        }
+
+        let destination = target.join(entry.file_name());
+        if is_dir {
+            count += copy_tree(&path, &destination, skip)?;
+        } else {
+            fs::copy(&path, &destination)
+                .map_err(|e| format!("无法复制 {}: {}", path.display(), e))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 复制项目数据目录（需求文档、对话、任务、docs/ 等）
+///
+/// 不复制 project.json 及其备份和关联目录的文件快照，也不复制应用内的 src/（源码单独复制）。
+pub fn copy_project_data(source_dir: &Path, target_dir: &Path, reset_history: bool) -> Result<usize, String> {
+    let managed_src = source_dir.join("src");
+    copy_tree(source_dir, target_dir, &|path, _| {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        path == managed_src
+            || name.starts_with("project.json")
+            || name == relink::SNAPSHOT_FILE
+            || (reset_history && HISTORY_FILES.contains(&name))
+    })
+}
+
+/// 复制源码，跳过依赖、构建产物和隐藏目录
+pub fn copy_source(source_root: &Path, target: &Path) -> Result<usize, String> {
+    if !source_root.is_dir() {
+        return Err(format!("代码目录不存在: {}", source_root.display()));
+    }
+    copy_tree(source_root, target, &|path, is_dir| ignore::is_ignored(path, is_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_project_data_resets_history() {
+        let base = std::env::temp_dir().join(format!("code-sensei-fork-{}", std::process::id()));
+        let source = base.join("source");
+        fs::create_dir_all(source.join("src/target")).unwrap();
+        fs::create_dir_all(source.join("docs")).unwrap();
+        fs::write(source.join("project.json"), "{}").unwrap();
+        fs::write(source.join("requirement.md"), "# 需求").unwrap();
+        fs::write(source.join("chat.json"), "[]").unwrap();
+        fs::write(source.join("docs/architecture.md"), "# 架构").unwrap();
+        fs::write(source.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(source.join("src/target/app"), "").unwrap();
+
+        let target = base.join("target");
+        let data_files = copy_project_data(&source, &target, true).unwrap();
+        let source_files = copy_source(&source.join("src"), &target.join("src")).unwrap();
+        let has_chat = target.join("chat.json").exists();
+        let has_meta = target.join("project.json").exists();
+        let has_build = target.join("src/target").exists();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(data_files, 2);
+        assert_eq!(source_files, 1);
+        assert!(!has_chat && !has_meta && !has_build);
+    }
+}
@@ -0,0 +1,334 @@
+// 需求与实现差距分析：把需求文档和按 token 预算裁剪的代码摘要交给 AI，得到每条需求的实现情况
+use crate::requirement::RequirementDoc;
+use crate::symbols;
+use crate::trace;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path};
+
+/// 代码摘要默认的 token 预算
+pub const DEFAULT_TOKEN_BUDGET: usize = 24_000;
+
+/// 最新一次分析结果保存的文件名（位于项目目录）
+const REPORT_FILE: &str = "gap_report.json";
+
+/// 单个文件完整内容最多占用预算的比例（分母）
+const MAX_FILE_SHARE: usize = 4;
+
+/// 提纲中每个文件最多保留的行数
+const OUTLINE_LINES: usize = 40;
+
+/// 识别定义行的开头，用于生成文件提纲
+const DEFINITION_PREFIXES: [&str; 20] = [
+    "def ", "async def ", "class ", "fn ", "pub fn ", "pub(crate) fn ", "async fn ", "pub async fn ", "struct ",
+    "pub struct ", "enum ", "pub enum ", "impl ", "trait ", "pub trait ", "function ", "async function ",
+    "export ", "interface ", "public ",
+];
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GapStatus {
+    Implemented,
+    Partial,
+    Missing,
+    /// AI 没有给出判断
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementGap {
+    pub id: String,
+    pub text: String,
+    pub status: GapStatus,
+    /// 作为依据的文件（相对路径），不存在的文件会被去掉
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// 代码中存在但需求文档中没有的功能
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraFeature {
+    pub description: String,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+}
+
+/// 代码摘要包含了哪些文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestSummary {
+    /// 完整内容
+    pub full: Vec<String>,
+    /// 只包含定义行
+    pub outlined: Vec<String>,
+    /// 只列出文件名
+    pub listed: Vec<String>,
+    pub estimated_tokens: usize,
+    pub token_budget: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GapReport {
+    pub generated_at: i64,
+    pub requirements: Vec<RequirementGap>,
+    pub extra_features: Vec<ExtraFeature>,
+    #[serde(default)]
+    pub summary: String,
+    pub implemented: usize,
+    pub partial: usize,
+    pub missing: usize,
+    pub digest: DigestSummary,
+}
+
+/// AI 回复的 JSON 结构
+#[derive(Debug, Deserialize)]
+struct RawReport {
+    #[serde(default)]
+    requirements: Vec<RawRequirement>,
+    #[serde(default)]
+    extra_features: Vec<ExtraFeature>,
+    #[serde(default)]
+    summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRequirement {
+    id: String,
+    status: String,
+    #[serde(default)]
+    evidence: Vec<String>,
+    #[serde(default)]
+    notes: String,
+}
+
+// ===== 代码摘要 =====
+
+/// 粗略估算 token 数：ASCII 字符约 4 个一个 token，其他字符（例如中文）按一个 token 计算
+pub fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(|c| c.is_ascii()).count();
+    let other = text.chars().count() - ascii;
+    ascii.div_ceil(4) + other
+}
+
+/// 文件提纲：支持的语言使用符号索引的声明行，其他文件按行首识别定义
+fn outline(file: &str, content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let definitions: Vec<usize> = match symbols::extract(file, content) {
+        Some(symbols) => symbols.iter().map(|s| s.line - 1).collect(),
+        None => (0..lines.len())
+            .filter(|i| {
+                let trimmed = lines[*i].trim_start();
+                DEFINITION_PREFIXES.iter().any(|p| trimmed.starts_with(p))
+            })
+            .collect(),
+    };
+    definitions
+        .into_iter()
+        .filter_map(|i| lines.get(i).map(|line| format!("{:>5}: {}", i + 1, line.trim_end())))
+        .take(OUTLINE_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 按预算生成代码摘要：入口文件和较小的文件优先放入完整内容，放不下的文件只保留定义行，再放不下的只列出路径
+pub fn build_digest(root: &Path, entry_points: &[String], token_budget: usize) -> (String, DigestSummary) {
+    let mut files = Vec::new();
+    trace::collect_code_files(root, root, &mut files);
+
+    let mut sized: Vec<(String, String)> = files
+        .into_iter()
+        .filter_map(|f| fs::read_to_string(root.join(&f)).ok().map(|c| (f, c)))
+        .collect();
+    sized.sort_by_key(|(f, c)| (!entry_points.contains(f), c.len(), f.clone()));
+
+    let mut summary = DigestSummary {
+        token_budget,
+        ..Default::default()
+    };
+    let mut used = 0;
+    let mut sections = Vec::new();
+    let mut deferred = Vec::new();
+
+    for (file, content) in sized {
+        let section = format!("### {}\n```\n{}\n```", file, content.trim_end());
+        let tokens = estimate_tokens(&section);
+        if tokens <= token_budget / MAX_FILE_SHARE && used + tokens <= token_budget {
+            used += tokens;
+            sections.push(section);
+            summary.full.push(file);
+        } else {
+            deferred.push((file, content));
+        }
+    }
+
+    for (file, content) in deferred {
+        let section = format!("### {}（只包含定义）\n```\n{}\n```", file, outline(&file, &content));
+        let tokens = estimate_tokens(&section);
+        if used + tokens <= token_budget {
+            used += tokens;
+            sections.push(section);
+            summary.outlined.push(file);
+        } else {
+            summary.listed.push(file);
+        }
+    }
+
+    if !summary.listed.is_empty() {
+        sections.push(format!("### 未包含内容的文件\n{}", summary.listed.join("\n")));
+    }
+    summary.estimated_tokens = used;
+    (sections.join("\n\n"), summary)
+}
+
+// ===== 提示词与解析 =====
+
+pub fn build_prompt(requirement: &str, digest: &str) -> String {
+    format!(
+        "你是 Code Sensei 的编程老师，正在检查学生的作业是否完成了需求文档中的要求。
+
+## 需求文档
+{}
+
+## 代码摘要
+{}
+
+## 任务
+逐条检查需求文档中带编号的需求（例如 FR-1、NFR-1、AC-1）在代码中的实现情况：
+- implemented：已完整实现
+- partial：只实现了一部分
+- missing：没有实现
+每条需求给出作为依据的文件路径和简短说明。另外列出代码中已经实现、但需求文档中没有提到的功能。
+
+## 输出格式
+只在一个代码块中输出 JSON，不要有其他说明：
+```json
+{{
+  \"requirements\": [{{\"id\": \"FR-1\", \"status\": \"implemented\", \"evidence\": [\"src/main.py\"], \"notes\": \"说明\"}}],
+  \"extra_features\": [{{\"description\": \"功能描述\", \"evidence\": [\"src/util.py\"]}}],
+  \"summary\": \"整体评价\"
+}}
+```",
+        requirement.trim_end(),
+        digest
+    )
+}
+
+fn parse_status(status: &str) -> GapStatus {
+    match status.trim().to_lowercase().as_str() {
+        "implemented" | "done" | "complete" => GapStatus::Implemented,
+        "partial" | "partially_implemented" => GapStatus::Partial,
+        "missing" | "not_implemented" => GapStatus::Missing,
+        _ => GapStatus::Unknown,
+    }
+}
+
+/// 解析 AI 的回复：按需求文档的条目生成结果，AI 漏掉的条目标记为 unknown，不存在或不在项目中的依据文件会被去掉
+pub fn parse_report(reply: &str, requirement: &str, root: &Path, digest: DigestSummary) -> Result<GapReport, String> {
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if end > start => &reply[start..=end],
+        _ => return Err("AI 没有返回分析结果".to_string()),
+    };
+    let raw: RawReport = serde_json::from_str(json).map_err(|e| format!("无法解析分析结果: {}", e))?;
+
+    let existing = |paths: &[String]| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.trim().trim_start_matches("./").replace('\\', "/"))
+            // 只接受项目内的相对路径
+            .filter(|p| Path::new(p).components().all(|c| matches!(c, Component::Normal(_))))
+            .filter(|p| !p.is_empty() && root.join(p).is_file())
+            .collect()
+    };
+
+    let requirements: Vec<RequirementGap> = trace::traceable_items(&RequirementDoc::parse(requirement))
+        .into_iter()
+        .map(|item| {
+            let found = raw.requirements.iter().find(|r| r.id.trim().eq_ignore_ascii_case(&item.id));
+            RequirementGap {
+                status: found.map(|r| parse_status(&r.status)).unwrap_or(GapStatus::Unknown),
+                evidence: found.map(|r| existing(&r.evidence)).unwrap_or_default(),
+                notes: found.map(|r| r.notes.trim().to_string()).unwrap_or_default(),
+                id: item.id,
+                text: item.text,
+            }
+        })
+        .collect();
+
+    let count = |status: GapStatus| requirements.iter().filter(|r| r.status == status).count();
+    Ok(GapReport {
+        generated_at: chrono::Utc::now().timestamp(),
+        implemented: count(GapStatus::Implemented),
+        partial: count(GapStatus::Partial),
+        missing: count(GapStatus::Missing),
+        extra_features: raw
+            .extra_features
+            .into_iter()
+            .filter(|f| !f.description.trim().is_empty())
+            .map(|f| ExtraFeature {
+                evidence: existing(&f.evidence),
+                description: f.description,
+            })
+            .collect(),
+        summary: raw.summary,
+        requirements,
+        digest,
+    })
+}
+
+// ===== 保存 =====
+
+pub fn save_report(project_dir: &Path, report: &GapReport) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(report).map_err(|e| format!("无法序列化分析结果: {}", e))?;
+    fs::write(project_dir.join(REPORT_FILE), content).map_err(|e| format!("无法保存分析结果: {}", e))
+}
+
+pub fn load_report(project_dir: &Path) -> Option<GapReport> {
+    let content = fs::read_to_string(project_dir.join(REPORT_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_respects_budget() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-gap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.py"), "def main():\n    print('hi')\n").unwrap();
+        let big = format!("class Big:\n{}", "    x = 1\n".repeat(2000));
+        fs::write(dir.join("big.py"), big).unwrap();
+
+        let (digest, summary) = build_digest(&dir, &["main.py".to_string()], 400);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.full, vec!["main.py"]);
+        assert_eq!(summary.outlined, vec!["big.py"]);
+        assert!(summary.estimated_tokens <= 400);
+        assert!(digest.contains("    1: class Big:"));
+    }
+
+    #[test]
+    fn test_parse_report_follows_requirement_doc() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-gap-parse-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("main.py"), "").unwrap();
+        let absolute = dir.join("main.py").display().to_string().replace('\\', "/");
+
+        let requirement = "# 计算器\n\n## 功能需求\n- 加法\n- 减法\n";
+        let reply = format!(
+            "```json\n{{\"requirements\": [{{\"id\": \"FR-1\", \"status\": \"implemented\", \"evidence\": [\"./main.py\", \"fake.py\", \"src/../main.py\", \"{}\"]}}], \"extra_features\": [{{\"description\": \"乘法\"}}]}}\n```",
+            absolute
+        );
+        let report = parse_report(&reply, requirement, &dir, DigestSummary::default()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.requirements.len(), 2);
+        assert_eq!(report.requirements[0].evidence, vec!["main.py"]);
+        assert_eq!(report.requirements[1].status, GapStatus::Unknown);
+        assert_eq!(report.implemented, 1);
+        assert_eq!(report.extra_features.len(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+// 需求文档版本历史：每次写入 requirement.md 时保存一个带来源的版本，支持对比和恢复
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 版本保存的目录（位于项目目录）
+const HISTORY_DIR: &str = "history/requirement";
+
+/// 版本列表中提示词预览的最大字符数
+const PROMPT_PREVIEW_CHARS: usize = 80;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSource {
+    /// 开始记录历史前已有的内容，或创建项目时生成的初始文档
+    Initial,
+    /// 用户在编辑器中修改
+    User,
+    /// AI 生成或修改
+    Ai,
+    /// 从历史版本恢复
+    Restore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub id: String,
+    /// 毫秒时间戳
+    pub created_at: i64,
+    pub source: RevisionSource,
+    /// AI 修改时用户的输入
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// 恢复操作的来源版本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevisionSummary {
+    pub id: String,
+    pub created_at: i64,
+    pub source: RevisionSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<String>,
+    pub bytes: usize,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevisionDiff {
+    pub from: String,
+    pub to: String,
+    /// unified diff 格式的差异
+    pub unified: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl Revision {
+    pub fn summary(&self) -> RevisionSummary {
+        RevisionSummary {
+            id: self.id.clone(),
+            created_at: self.created_at,
+            source: self.source,
+            prompt: self.prompt.as_ref().map(|p| {
+                let preview: String = p.chars().take(PROMPT_PREVIEW_CHARS).collect();
+                if preview.len() < p.len() { format!("{}...", preview) } else { preview }
+            }),
+            restored_from: self.restored_from.clone(),
+            bytes: self.content.len(),
+            lines: self.content.lines().count(),
+        }
+    }
+}
+
+// ===== 读写 =====
+
+fn history_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(HISTORY_DIR)
+}
+
+/// 同一毫秒内的版本 id 带有递增后缀（`<毫秒>-<序号>`），按数字比较序号
+fn id_suffix(id: &str) -> u32 {
+    id.rsplit_once('-').and_then(|(_, n)| n.parse().ok()).unwrap_or(0)
+}
+
+/// 读取全部版本，按时间从旧到新排列
+pub fn revisions(project_dir: &Path) -> Vec<Revision> {
+    let Ok(entries) = fs::read_dir(history_dir(project_dir)) else { return Vec::new() };
+
+    let mut revisions: Vec<Revision> = entries
+        .flatten()
+        .filter(|e| e.path().extension().map(|x| x == "json").unwrap_or(false))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    revisions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| id_suffix(&a.id).cmp(&id_suffix(&b.id))));
+    revisions
+}
+
+/// 保存一个新版本，内容与最新版本相同时不保存
+pub fn record(
+    project_dir: &Path,
+    content: &str,
+    source: RevisionSource,
+    prompt: Option<&str>,
+    restored_from: Option<&str>,
+) -> Result<Option<RevisionSummary>, String> {
+    if revisions(project_dir).last().map(|r| r.content == content).unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let dir = history_dir(project_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建版本历史目录: {}", e))?;
+
+    // 同一毫秒内的多次写入使用递增的 id
+    let created_at = chrono::Utc::now().timestamp_millis();
+    let mut id = created_at.to_string();
+    let mut suffix = 1;
+    while dir.join(format!("{}.json", id)).exists() {
+        id = format!("{}-{}", created_at, suffix);
+        suffix += 1;
+    }
+
+    let revision = Revision {
+        id: id.clone(),
+        created_at,
+        source,
+        prompt: prompt.map(|p| p.to_string()),
+        restored_from: restored_from.map(|r| r.to_string()),
+        content: content.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&revision)
+        .map_err(|e| format!("无法序列化版本: {}", e))?;
+    fs::write(dir.join(format!("{}.json", id)), json).map_err(|e| format!("无法保存版本: {}", e))?;
+
+    Ok(Some(revision.summary()))
+}
+
+/// 写入需求文档并保存版本
+///
+/// 还没有任何历史时，先把文件中原有的内容保存为初始版本，避免第一次写入就丢失旧内容。
+pub fn write_requirement(
+    project_dir: &Path,
+    path: &Path,
+    content: &str,
+    source: RevisionSource,
+    prompt: Option<&str>,
+) -> Result<Option<RevisionSummary>, String> {
+    if revisions(project_dir).is_empty() {
+        if let Ok(previous) = fs::read_to_string(path) {
+            if !previous.trim().is_empty() && previous != content {
+                record(project_dir, &previous, RevisionSource::Initial, None, None)?;
+            }
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("无法保存需求文档: {}", e))?;
+
+    record(project_dir, content, source, prompt, None)
+}
+
+/// 版本列表，最新的在前
+pub fn list(project_dir: &Path) -> Vec<RevisionSummary> {
+    revisions(project_dir).iter().rev().map(|r| r.summary()).collect()
+}
+
+pub fn load(project_dir: &Path, id: &str) -> Result<Revision, String> {
+    if id.contains(['/', '\\', '.']) {
+        return Err(format!("无效的版本 id: {}", id));
+    }
+    let content = fs::read_to_string(history_dir(project_dir).join(format!("{}.json", id)))
+        .map_err(|_| format!("版本不存在: {}", id))?;
+    serde_json::from_str(&content).map_err(|e| format!("无法解析版本 {}: {}", id, e))
+}
+
+// ===== 对比 =====
+
+/// 对比两段内容，`from` 和 `to` 为显示在 diff 头部的名称
+pub fn diff(old: &str, new: &str, from: &str, to: &str) -> RevisionDiff {
+    let text_diff = TextDiff::from_lines(old, new);
+
+    let mut added = 0;
+    let mut removed = 0;
+    for change in text_diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => added += 1,
+            ChangeTag::Delete => removed += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let unified = text_diff
+        .unified_diff()
+        .context_radius(3)
+        .header(from, to)
+        .to_string();
+
+    RevisionDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        unified,
+        added,
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_requirement_keeps_initial_content() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("requirement.md");
+        fs::write(&path, "# 旧需求\n").unwrap();
+
+        write_requirement(&dir, &path, "# 新需求\n", RevisionSource::Ai, Some("改成新需求")).unwrap();
+        let unchanged = write_requirement(&dir, &path, "# 新需求\n", RevisionSource::User, None).unwrap();
+        let revisions = list(&dir);
+        let initial = load(&dir, &revisions[1].id).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(unchanged.is_none());
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].source, RevisionSource::Ai);
+        assert_eq!(revisions[0].prompt.as_deref(), Some("改成新需求"));
+        assert_eq!(initial.content, "# 旧需求\n");
+    }
+
+    #[test]
+    fn test_revisions_sort_suffix_numerically() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-history-order-{}", std::process::id()));
+        fs::create_dir_all(history_dir(&dir)).unwrap();
+        for id in ["5-10", "5", "5-2"] {
+            let revision = Revision {
+                id: id.to_string(),
+                created_at: 5,
+                source: RevisionSource::User,
+                prompt: None,
+                restored_from: None,
+                content: id.to_string(),
+            };
+            fs::write(history_dir(&dir).join(format!("{}.json", id)), serde_json::to_string(&revision).unwrap()).unwrap();
+        }
+
+        let ids: Vec<String> = revisions(&dir).into_iter().map(|r| r.id).collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ids, vec!["5", "5-2", "5-10"]);
+    }
+
+    #[test]
+    fn test_diff_counts_lines() {
+        let result = diff("a\nb\nc\n", "a\nB\nc\nd\n", "v1", "v2");
+        assert_eq!(result.added, 2);
+        assert_eq!(result.removed, 1);
+        assert!(result.unified.contains("-b\n+B\n"));
+    }
+}
@@ -0,0 +1,55 @@
+// 文件过滤规则：文件树、扫描等功能共用的跳过目录、隐藏文件和二进制文件规则
+use std::path::Path;
+
+/// 应该跳过的常见大型目录
+pub const SKIP_DIRS: [&str; 16] = [
+    "node_modules",
+    ".git",
+    "target",
+    "debug",
+    "release",
+    "build",
+    "dist",
+    ".vscode",
+    ".idea",
+    "vendor",
+    "venv",
+    ".venv",
+    "__pycache__",
+    ".next",
+    ".nuxt",
+    "coverage",
+];
+
+/// 跳过的二进制文件扩展名
+pub const SKIP_EXTENSIONS: [&str; 9] = ["dll", "exe", "so", "dylib", "bin", "pdb", "o", "a", "lib"];
+
+/// 隐藏文件中仍然需要显示的文件
+const VISIBLE_DOTFILES: [&str; 2] = [".gitignore", ".env"];
+
+/// 是否为需要跳过的隐藏文件或目录（以.开头）
+pub fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') && !VISIBLE_DOTFILES.contains(&name)
+}
+
+/// 是否为需要跳过的目录名
+pub fn is_skipped_dir(name: &str) -> bool {
+    SKIP_DIRS.contains(&name)
+}
+
+/// 是否为需要跳过的二进制文件
+pub fn is_skipped_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|ext| SKIP_EXTENSIONS.contains(&ext))
+        .unwrap_or(false)
+}
+
+/// 综合判断一个目录项是否应被忽略
+pub fn is_ignored(path: &Path, is_dir: bool) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if is_hidden(name) || is_skipped_dir(name) {
+        return true;
+    }
+    !is_dir && is_skipped_file(path)
+}
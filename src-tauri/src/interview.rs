@@ -0,0 +1,381 @@
+// 需求访谈：由 OpenCode 逐个提出澄清问题，收集用户、功能、约束和验收标准后再写需求文档
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 访谈状态保存的文件名（位于项目目录），每一步都会写入，应用重启后可以继续
+const SESSION_FILE: &str = "interview.json";
+
+/// 访谈记录保存的文件名（与 requirement.md 位于同一目录）
+pub const TRANSCRIPT_FILE: &str = "requirement-interview.md";
+
+/// 最多提问的数量，达到后直接进入生成文档阶段
+pub const MAX_QUESTIONS: usize = 12;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InterviewTopic {
+    Users,
+    Features,
+    Constraints,
+    Acceptance,
+}
+
+pub const TOPICS: [InterviewTopic; 4] = [
+    InterviewTopic::Users,
+    InterviewTopic::Features,
+    InterviewTopic::Constraints,
+    InterviewTopic::Acceptance,
+];
+
+impl InterviewTopic {
+    pub fn name(self) -> &'static str {
+        match self {
+            InterviewTopic::Users => "目标用户",
+            InterviewTopic::Features => "核心功能",
+            InterviewTopic::Constraints => "约束条件",
+            InterviewTopic::Acceptance => "验收标准",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            InterviewTopic::Users => "users",
+            InterviewTopic::Features => "features",
+            InterviewTopic::Constraints => "constraints",
+            InterviewTopic::Acceptance => "acceptance",
+        }
+    }
+
+    /// AI 没有给出问题时使用的默认问题
+    fn fallback_question(self) -> &'static str {
+        match self {
+            InterviewTopic::Users => "这个程序是给谁用的？他们会在什么场景下使用它？",
+            InterviewTopic::Features => "用户最需要用它完成哪几件事？请按重要程度列出来。",
+            InterviewTopic::Constraints => "有没有必须遵守的限制，例如运行环境、使用的语言或库、数据存储方式、性能要求？",
+            InterviewTopic::Acceptance => "做到什么程度就算完成了？请举一两个可以用来检验的具体例子。",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<InterviewTopic> {
+        TOPICS.iter().copied().find(|t| t.key() == key.trim().to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InterviewStatus {
+    /// 等待用户回答当前问题
+    Asking,
+    /// 问题已问完，等待生成需求文档
+    ReadyToWrite,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterviewTurn {
+    pub topic: InterviewTopic,
+    pub question: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    pub asked_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answered_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterviewSession {
+    /// 用户最初的一句话描述
+    pub idea: String,
+    pub status: InterviewStatus,
+    pub turns: Vec<InterviewTurn>,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+/// AI 对下一步的回复
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextStep {
+    Ask(InterviewTopic, String),
+    Done,
+}
+
+impl InterviewSession {
+    pub fn new(idea: &str) -> InterviewSession {
+        let now = chrono::Utc::now().timestamp();
+        InterviewSession {
+            idea: idea.trim().to_string(),
+            status: InterviewStatus::Asking,
+            turns: Vec::new(),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// 等待回答的问题
+    pub fn pending(&self) -> Option<&InterviewTurn> {
+        self.turns.last().filter(|t| t.answer.is_none())
+    }
+
+    /// 还没有问过的话题
+    pub fn uncovered_topics(&self) -> Vec<InterviewTopic> {
+        TOPICS
+            .iter()
+            .copied()
+            .filter(|topic| !self.turns.iter().any(|t| t.topic == *topic && t.answer.is_some()))
+            .collect()
+    }
+
+    pub fn answer(&mut self, answer: &str) -> Result<(), String> {
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Err("回答不能为空".to_string());
+        }
+        let now = chrono::Utc::now().timestamp();
+        let turn = self
+            .turns
+            .last_mut()
+            .filter(|t| t.answer.is_none())
+            .ok_or_else(|| "当前没有需要回答的问题".to_string())?;
+        turn.answer = Some(answer.to_string());
+        turn.answered_at = Some(now);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// 根据 AI 的回复进入下一步
+    ///
+    /// AI 认为可以结束但还有话题没问到时，改问该话题的默认问题；达到提问上限时直接结束。
+    pub fn advance(&mut self, step: NextStep) {
+        let now = chrono::Utc::now().timestamp();
+        self.updated_at = now;
+
+        if self.turns.len() >= MAX_QUESTIONS {
+            self.status = InterviewStatus::ReadyToWrite;
+            return;
+        }
+
+        let uncovered = self.uncovered_topics();
+        let (topic, question) = match step {
+            NextStep::Ask(topic, question) => (topic, question),
+            NextStep::Done => match uncovered.first() {
+                Some(topic) => (*topic, topic.fallback_question().to_string()),
+                None => {
+                    self.status = InterviewStatus::ReadyToWrite;
+                    return;
+                }
+            },
+        };
+
+        self.turns.push(InterviewTurn {
+            topic,
+            question,
+            answer: None,
+            asked_at: now,
+            answered_at: None,
+        });
+        self.status = InterviewStatus::Asking;
+    }
+}
+
+// ===== 保存 =====
+
+pub fn load(project_dir: &Path) -> Option<InterviewSession> {
+    let content = fs::read_to_string(project_dir.join(SESSION_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save(project_dir: &Path, session: &InterviewSession) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(session).map_err(|e| format!("无法序列化访谈记录: {}", e))?;
+    fs::write(project_dir.join(SESSION_FILE), content).map_err(|e| format!("无法保存访谈记录: {}", e))
+}
+
+pub fn remove(project_dir: &Path) -> Result<(), String> {
+    let path = project_dir.join(SESSION_FILE);
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| format!("无法删除访谈记录: {}", e))?;
+    }
+    Ok(())
+}
+
+// ===== 提示词 =====
+
+fn transcript_text(session: &InterviewSession) -> String {
+    session
+        .turns
+        .iter()
+        .filter_map(|t| {
+            t.answer
+                .as_ref()
+                .map(|a| format!("[{}] 问：{}\n答：{}", t.topic.name(), t.question, a))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 构建提出下一个问题的提示词
+pub fn build_question_prompt(project_name: &str, session: &InterviewSession) -> String {
+    let uncovered = session
+        .uncovered_topics()
+        .iter()
+        .map(|t| format!("{}（{}）", t.name(), t.key()))
+        .collect::<Vec<_>>()
+        .join("、");
+    let transcript = transcript_text(session);
+
+    format!(
+        "你是 Code Sensei 的需求分析老师，正在通过提问帮助一位编程初学者把想法整理成需求。
+
+## 项目名称
+{}
+
+## 学生最初的想法
+{}
+
+## 已经问过的问题
+{}
+
+## 还没有问到的话题
+{}
+
+## 任务
+提出**一个**最重要的澄清问题。依次覆盖目标用户、核心功能、约束条件和验收标准；回答已经足够清楚的话题不要重复问。
+所有话题都已经清楚时回复 DONE。
+
+## 输出格式
+严格按照以下格式输出，不要有其他内容：
+TOPIC: users|features|constraints|acceptance
+QUESTION: 问题内容
+
+或者只输出一行：
+DONE",
+        project_name,
+        session.idea,
+        if transcript.is_empty() { "（还没有）".to_string() } else { transcript },
+        if uncovered.is_empty() { "（都已问到）".to_string() } else { uncovered }
+    )
+}
+
+/// 解析 AI 的回复，无法识别格式时把整段回复作为下一个未覆盖话题的问题
+pub fn parse_next_step(reply: &str, session: &InterviewSession) -> NextStep {
+    let reply = reply.trim();
+    if reply.lines().any(|l| l.trim().trim_matches('*').eq_ignore_ascii_case("DONE")) && !reply.contains("QUESTION:") {
+        return NextStep::Done;
+    }
+
+    let mut topic = None;
+    let mut question = None;
+    for line in reply.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("TOPIC:") {
+            topic = InterviewTopic::from_key(value);
+        } else if let Some(value) = line.strip_prefix("QUESTION:") {
+            question = Some(value.trim().to_string());
+        } else if let Some(ref mut q) = question {
+            // 问题可能跨多行
+            if !line.is_empty() {
+                q.push('\n');
+                q.push_str(line);
+            }
+        }
+    }
+
+    let default_topic = session.uncovered_topics().first().copied().unwrap_or(InterviewTopic::Features);
+    match question.filter(|q| !q.is_empty()) {
+        Some(question) => NextStep::Ask(topic.unwrap_or(default_topic), question),
+        None if reply.is_empty() => NextStep::Done,
+        None => NextStep::Ask(default_topic, reply.to_string()),
+    }
+}
+
+/// 构建根据访谈记录写需求文档的提示词
+pub fn build_requirement_prompt(project_name: &str, language: &str, session: &InterviewSession) -> String {
+    format!(
+        "你是 Code Sensei 的需求文档编辑助手。请根据下面的访谈记录为项目「{}」写一份需求文档。
+
+## 编程语言
+{}
+
+## 学生最初的想法
+{}
+
+## 访谈记录
+{}
+
+## 输出格式
+严格按照 Markdown 格式输出完整的需求文档，包含：
+- 项目描述（包括目标用户）
+- 功能需求
+- 非功能需求（约束条件）
+- 技术栈
+- 验收标准
+- 待确认问题（访谈中没有说清楚的地方）
+
+只使用访谈中提到的信息，不要编造学生没有提到的功能。
+请直接输出需求文档内容，不要有其他说明。",
+        project_name,
+        language,
+        session.idea,
+        transcript_text(session)
+    )
+}
+
+/// 可读的访谈记录，与需求文档保存在一起
+pub fn render_transcript(session: &InterviewSession) -> String {
+    let started = chrono::DateTime::from_timestamp(session.started_at, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+
+    let mut out = format!("# 需求访谈记录\n\n开始时间：{}\n\n## 最初的想法\n{}\n", started, session.idea);
+    for (i, turn) in session.turns.iter().enumerate() {
+        out.push_str(&format!(
+            "\n## 问题 {}（{}）\n{}\n\n**回答：** {}\n",
+            i + 1,
+            turn.topic.name(),
+            turn.question,
+            turn.answer.as_deref().unwrap_or("（未回答）")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_next_step() {
+        let session = InterviewSession::new("做一个记账本");
+        assert_eq!(
+            parse_next_step("TOPIC: features\nQUESTION: 需要记录哪些信息？", &session),
+            NextStep::Ask(InterviewTopic::Features, "需要记录哪些信息？".to_string())
+        );
+        assert_eq!(parse_next_step("DONE", &session), NextStep::Done);
+        assert_eq!(
+            parse_next_step("谁会使用这个记账本？", &session),
+            NextStep::Ask(InterviewTopic::Users, "谁会使用这个记账本？".to_string())
+        );
+    }
+
+    #[test]
+    fn test_done_requires_all_topics() {
+        let mut session = InterviewSession::new("做一个记账本");
+        session.advance(NextStep::Ask(InterviewTopic::Users, "谁来用？".to_string()));
+        session.answer("我自己").unwrap();
+
+        // 还有话题没问到，继续提问
+        session.advance(NextStep::Done);
+        assert_eq!(session.status, InterviewStatus::Asking);
+        assert_eq!(session.pending().unwrap().topic, InterviewTopic::Features);
+
+        for topic in [InterviewTopic::Features, InterviewTopic::Constraints, InterviewTopic::Acceptance] {
+            session.turns.last_mut().unwrap().topic = topic;
+            session.answer("回答").unwrap();
+            session.advance(NextStep::Done);
+        }
+        assert_eq!(session.status, InterviewStatus::ReadyToWrite);
+        assert!(render_transcript(&session).contains("## 问题 4（验收标准）"));
+    }
+}
@@ -0,0 +1,215 @@
+// 项目语言检测：根据文件扩展名和项目清单统计项目使用的语言
+use crate::ignore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 统计时最多扫描的文件数
+const MAX_SCAN_FILES: usize = 5000;
+
+/// 统计时最大扫描深度
+const MAX_SCAN_DEPTH: u32 = 10;
+
+/// 没有任何代码文件时使用的默认语言
+pub const DEFAULT_LANGUAGE: &str = "Python";
+
+// ===== 数据结构 =====
+
+/// 单个语言在项目中的占比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageStat {
+    pub name: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// 按字节数计算的占比（0-100）
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageReport {
+    pub primary: String,
+    pub breakdown: Vec<LanguageStat>,
+    /// 在项目根目录中找到的清单文件，例如 Cargo.toml
+    pub manifests: Vec<String>,
+}
+
+// ===== 识别规则 =====
+
+/// 根据扩展名识别语言
+pub fn language_for_extension(ext: &str) -> Option<&'static str> {
+    let language = match ext.to_lowercase().as_str() {
+        "py" | "pyw" => "Python",
+        "rs" => "Rust",
+        "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "c" | "h" => "C",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "swift" => "Swift",
+        "sh" | "bash" => "Shell",
+        "vue" => "Vue",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// 标记语言只在没有其他代码时才作为主语言
+fn is_markup(language: &str) -> bool {
+    matches!(language, "HTML" | "CSS")
+}
+
+/// 根目录中的清单文件及其对应的语言，按优先级排序
+fn manifest_languages(root: &Path) -> Vec<(String, &'static str)> {
+    let mut found = Vec::new();
+
+    if root.join("Cargo.toml").is_file() {
+        found.push(("Cargo.toml".to_string(), "Rust"));
+    }
+    if root.join("go.mod").is_file() {
+        found.push(("go.mod".to_string(), "Go"));
+    }
+    if root.join("package.json").is_file() {
+        let typescript = root.join("tsconfig.json").is_file()
+            || fs::read_to_string(root.join("package.json"))
+                .map(|content| content.contains("\"typescript\""))
+                .unwrap_or(false);
+        found.push(("package.json".to_string(), if typescript { "TypeScript" } else { "JavaScript" }));
+    }
+    for manifest in ["pyproject.toml", "requirements.txt", "setup.py"] {
+        if root.join(manifest).is_file() {
+            found.push((manifest.to_string(), "Python"));
+        }
+    }
+    for manifest in ["pom.xml", "build.gradle", "build.gradle.kts"] {
+        if root.join(manifest).is_file() {
+            found.push((manifest.to_string(), "Java"));
+        }
+    }
+
+    found
+}
+
+// ===== 扫描 =====
+
+fn collect_stats(
+    dir: &Path,
+    depth: u32,
+    file_count: &mut usize,
+    stats: &mut HashMap<&'static str, (usize, u64)>,
+) {
+    if depth > MAX_SCAN_DEPTH {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    for entry in entries.flatten() {
+        if *file_count >= MAX_SCAN_FILES {
+            return;
+        }
+
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            collect_stats(&path, depth + 1, file_count, stats);
+            continue;
+        }
+
+        *file_count += 1;
+        let Some(language) = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(language_for_extension)
+        else {
+            continue;
+        };
+
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let stat = stats.entry(language).or_insert((0, 0));
+        stat.0 += 1;
+        stat.1 += size;
+    }
+}
+
+/// 检测项目语言，没有识别到任何语言时返回 None
+pub fn detect_languages(root: &Path) -> Option<LanguageReport> {
+    let mut stats = HashMap::new();
+    let mut file_count = 0;
+    collect_stats(root, 0, &mut file_count, &mut stats);
+
+    let manifests = manifest_languages(root);
+
+    let total_bytes: u64 = stats.values().map(|(_, bytes)| bytes).sum();
+    let mut breakdown: Vec<LanguageStat> = stats
+        .into_iter()
+        .map(|(name, (files, bytes))| LanguageStat {
+            name: name.to_string(),
+            files,
+            bytes,
+            percent: if total_bytes > 0 {
+                (bytes as f64 * 1000.0 / total_bytes as f64).round() / 10.0
+            } else {
+                0.0
+            },
+        })
+        .collect();
+    breakdown.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+    // 清单文件最能说明项目类型，其次是代码量最多的非标记语言
+    let primary = manifests
+        .first()
+        .map(|(_, language)| language.to_string())
+        .or_else(|| {
+            breakdown
+                .iter()
+                .find(|s| !is_markup(&s.name))
+                .or_else(|| breakdown.first())
+                .map(|s| s.name.clone())
+        })?;
+
+    Some(LanguageReport {
+        primary,
+        breakdown,
+        manifests: manifests.into_iter().map(|(name, _)| name).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_for_extension() {
+        assert_eq!(language_for_extension("py"), Some("Python"));
+        assert_eq!(language_for_extension("TSX"), Some("TypeScript"));
+        assert_eq!(language_for_extension("md"), None);
+    }
+
+    #[test]
+    fn test_detect_languages_prefers_manifest() {
+        let root = std::env::temp_dir().join(format!("code-sensei-lang-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("tool.py"), "print('a much longer python helper script')\n").unwrap();
+
+        let report = detect_languages(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.primary, "Rust");
+        assert_eq!(report.manifests, vec!["Cargo.toml"]);
+        assert_eq!(report.breakdown.len(), 2);
+    }
+}
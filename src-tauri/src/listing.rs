@@ -0,0 +1,185 @@
+// 分页目录列表：每次只读取一个目录，展开文件夹时再加载下一层，用于大型项目的文件树
+use crate::ignore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path};
+
+/// 每页默认的条目数
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+/// 每页最多的条目数
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// 统计子目录条目数时最多数到的数量，超过时标记为已截断
+const MAX_CHILD_COUNT: usize = 10_000;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub name: String,
+    /// 相对于项目根目录的路径
+    pub path: String,
+    pub is_file: bool,
+    /// 文件夹中可见的条目数，文件为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<usize>,
+    /// 条目太多没有数完，`child_count` 为已数到的数量
+    #[serde(default)]
+    pub child_count_truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryPage {
+    /// 列出的目录，根目录为空字符串
+    pub path: String,
+    pub entries: Vec<DirEntry>,
+    /// 目录中可见的条目总数
+    pub total: usize,
+    /// 还有未返回的条目，用 `next_cursor` 读取下一页
+    pub has_more: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+// ===== 列表 =====
+
+/// 排序键：文件夹在前，同类按名称排序，与文件树一致
+fn sort_key(is_file: bool, name: &str) -> String {
+    format!("{}:{}", if is_file { "f" } else { "d" }, name)
+}
+
+/// 目录中未被忽略的条目：(名称, 是否为文件)
+fn visible_entries(dir: &Path) -> Result<Vec<(String, bool)>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("无法读取目录: {}", e))?;
+    let mut visible: Vec<(String, bool)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            if ignore::is_ignored(&path, is_dir) {
+                return None;
+            }
+            Some((entry.file_name().to_str()?.to_string(), !is_dir))
+        })
+        .collect();
+    visible.sort_by_cached_key(|(name, is_file)| sort_key(*is_file, name));
+    Ok(visible)
+}
+
+/// 统计文件夹中可见的条目数，返回 (数量, 是否截断)
+fn count_children(dir: &Path) -> (usize, bool) {
+    let Ok(entries) = fs::read_dir(dir) else { return (0, false) };
+    let mut count = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if ignore::is_ignored(&path, path.is_dir()) {
+            continue;
+        }
+        if count == MAX_CHILD_COUNT {
+            return (count, true);
+        }
+        count += 1;
+    }
+    (count, false)
+}
+
+/// 列出 `root` 下的一个目录；`cursor` 为上一页返回的 `next_cursor`
+pub fn list_dir(root: &Path, relative: &str, cursor: Option<&str>, limit: usize) -> Result<DirectoryPage, String> {
+    let relative = relative.replace('\\', "/").trim_matches('/').to_string();
+    // 只允许普通的路径组成部分，Windows 上的 C:/ 等前缀也会跳出根目录
+    if !Path::new(&relative).components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("无效的目录路径: {}", relative));
+    }
+    let dir = root.join(&relative);
+    // 新项目的源码目录可能还没有创建
+    if relative.is_empty() && !dir.exists() {
+        return Ok(DirectoryPage {
+            path: relative,
+            entries: Vec::new(),
+            total: 0,
+            has_more: false,
+            next_cursor: None,
+        });
+    }
+    if !dir.is_dir() {
+        return Err(format!("目录不存在: {}", relative));
+    }
+
+    let entries = visible_entries(&dir)?;
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    // 按排序键定位，翻页期间有文件增删时不会重复或跳过其他条目
+    let start = cursor
+        .map(|c| entries.partition_point(|(name, is_file)| sort_key(*is_file, name).as_str() <= c))
+        .unwrap_or(0);
+    let end = (start + limit).min(entries.len());
+
+    let page: Vec<DirEntry> = entries[start..end]
+        .iter()
+        .map(|(name, is_file)| {
+            let path = if relative.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", relative, name)
+            };
+            let (child_count, child_count_truncated) = if *is_file {
+                (None, false)
+            } else {
+                let (count, truncated) = count_children(&dir.join(name));
+                (Some(count), truncated)
+            };
+            DirEntry {
+                name: name.clone(),
+                path,
+                is_file: *is_file,
+                child_count,
+                child_count_truncated,
+            }
+        })
+        .collect();
+
+    let has_more = end < entries.len();
+    Ok(DirectoryPage {
+        next_cursor: page.last().filter(|_| has_more).map(|e| sort_key(e.is_file, &e.name)),
+        path: relative,
+        total: entries.len(),
+        has_more,
+        entries: page,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_dir_pages_with_cursor() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-listing-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/pkg")).unwrap();
+        fs::create_dir_all(dir.join("node_modules/lib")).unwrap();
+        fs::write(dir.join("src/main.py"), "").unwrap();
+        fs::write(dir.join("src/.hidden"), "").unwrap();
+        for name in ["a.py", "b.py", "c.py", "tool.exe"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let first = list_dir(&dir, "", None, 2).unwrap();
+        let second = list_dir(&dir, "", first.next_cursor.as_deref(), 2).unwrap();
+        let nested = list_dir(&dir, "src", None, 10).unwrap();
+        let escaped = list_dir(&dir, "../", None, 10);
+        let current = list_dir(&dir, "./src", None, 10);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names = |page: &DirectoryPage| page.entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+        assert_eq!(first.total, 4);
+        assert_eq!(names(&first), vec!["src", "a.py"]);
+        assert_eq!(first.entries[0].child_count, Some(2));
+        assert!(first.has_more);
+        assert_eq!(names(&second), vec!["b.py", "c.py"]);
+        assert!(!second.has_more && second.next_cursor.is_none());
+        assert_eq!(nested.entries[0].path, "src/pkg");
+        assert_eq!(nested.entries[1].path, "src/main.py");
+        assert!(escaped.is_err());
+        assert!(current.is_err());
+    }
+}
@@ -1,11 +1,39 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod acceptance;
+mod archive;
+mod catalog;
 mod config;
+mod diagnostics;
+mod docs;
+mod fork;
+mod gap;
+mod history;
+mod ignore;
+mod interview;
+mod language;
+mod listing;
+mod migration;
+mod onboarding;
 mod opencode;
+mod recovery;
+mod relink;
+mod replace;
+mod requirement;
+mod runner;
+mod sanitize;
+mod search;
+mod symbols;
+mod templates;
+mod testing;
+mod trace;
+mod watcher;
 
 use std::fs;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use tauri::{Manager, Emitter};
@@ -15,20 +43,69 @@ use opencode::OpenCodeClient;
 #[derive(Clone)]
 struct AppState {
     projects_dir: PathBuf,
+    templates_dir: PathBuf,
+    /// 损坏项目的隔离目录
+    quarantine_dir: PathBuf,
+    runner: runner::RunnerRegistry,
+    searches: search::SearchRegistry,
+    watchers: watcher::WatcherRegistry,
+    /// 异步 Agent 任务，按会话 id 保存
+    agent_tasks: Arc<Mutex<HashMap<String, AgentTask>>>,
+}
+
+/// Agent 任务开始时记录的信息，任务完成后用来处理修改过的文件
+struct AgentTask {
+    project_id: String,
+    /// 项目代码目录及开始时各文件的修改时间
+    code_root: PathBuf,
+    mtimes_before: HashMap<String, std::time::SystemTime>,
+    /// 开始时的需求文档和用户输入，用于把修改关联到需求编号
+    requirement: String,
+    user_input: String,
 }
 
 // ===== 数据模型 =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
+    /// project.json 的结构版本，见 migration 模块
+    #[serde(default)]
+    pub schema_version: u32,
     pub id: String,
     pub name: String,
     pub description: String,
+    /// 主语言
     pub language: String,
+    /// 各语言占比，按代码量从多到少排列
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<language::LanguageStat>,
     pub created_at: i64,
     pub updated_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub root_path: Option<String>,
+    /// 运行项目的命令，未设置时自动查找入口
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_command: Option<String>,
+    /// 运行测试的命令，来自创建项目时使用的模板
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 置顶的项目在列表中始终排在前面
+    #[serde(default)]
+    pub pinned: bool,
+    /// 归档的项目默认不在列表中显示
+    #[serde(default)]
+    pub archived: bool,
+    /// 复制来源项目的 id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    /// 从归档导入时归档中记录的原项目 id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+    /// 关联目录已不存在，扫描时计算，不保存
+    #[serde(default, skip_deserializing, skip_serializing_if = "std::ops::Not::not")]
+    pub root_missing: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +115,9 @@ pub struct FileNode {
     pub is_file: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<FileNode>>,
+    /// 文件夹因深度或文件数量限制没有完整列出，需要用 list_directory 加载
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,45 +146,25 @@ pub struct AgentResponse {
     pub document_content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// 写入需求文档前对 AI 输出所做的整理
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<sanitize::CleanupReport>,
 }
 
 // ===== Tauri Commands =====
 
 #[tauri::command]
-fn scan_projects(state: tauri::State<'_, AppState>) -> Result<Vec<Project>, String> {
+fn scan_projects(state: tauri::State<'_, AppState>) -> Result<recovery::ProjectScan, String> {
     let projects_dir = &state.projects_dir;
 
     if !projects_dir.exists() {
         fs::create_dir_all(projects_dir)
             .map_err(|e| format!("Failed to create projects directory: {}", e))?;
-        return Ok(vec![]);
-    }
-
-    let mut projects = Vec::new();
-
-    let entries = fs::read_dir(projects_dir)
-        .map_err(|e| format!("Failed to read projects directory: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            let meta_file = path.join("project.json");
-            if meta_file.exists() {
-                let content = fs::read_to_string(&meta_file)
-                    .map_err(|e| format!("Failed to read project.json: {}", e))?;
-
-                let project: Project = serde_json::from_str(&content)
-                    .map_err(|e| format!("Failed to parse project.json: {}", e))?;
-
-                projects.push(project);
-            }
-        }
+        return Ok(recovery::ProjectScan { projects: vec![], needs_repair: vec![] });
     }
 
-    projects.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-    Ok(projects)
+    // 旧版本的 project.json 在读取时自动迁移，损坏的项目单独列出
+    recovery::scan(projects_dir)
 }
 
 #[tauri::command]
@@ -113,8 +173,27 @@ fn create_project(
     name: String,
     description: String,
     root_path: Option<String>,
+    template_id: Option<String>,
+) -> Result<Project, String> {
+    new_project(&state, name, description, root_path, template_id)
+}
+
+/// 创建项目目录、元数据和初始需求文档
+fn new_project(
+    state: &AppState,
+    name: String,
+    description: String,
+    root_path: Option<String>,
+    template_id: Option<String>,
 ) -> Result<Project, String> {
-    let id = chrono::Utc::now().timestamp_millis().to_string();
+    let template = match template_id.as_deref() {
+        Some(id) if !id.is_empty() && id != templates::EMPTY_TEMPLATE_ID => {
+            Some(templates::find_template(&state.templates_dir, id)?)
+        }
+        _ => None,
+    };
+
+    let id = new_project_id();
     let project_dir = state.projects_dir.join(&id);
 
     // 创建项目目录结构
@@ -130,16 +209,46 @@ fn create_project(
             .map_err(|e| format!("Failed to create docs directory: {}", e))?;
     }
 
+    // 写入模板的入门文件（关联目录中已存在的文件不会被覆盖）
+    let code_dir = match root_path {
+        Some(ref root) => PathBuf::from(root),
+        None => project_dir.join("src"),
+    };
+    if let Some(ref template) = template {
+        if let Err(e) = templates::apply_template(template, &code_dir) {
+            let _ = fs::remove_dir_all(&project_dir);
+            return Err(e);
+        }
+    }
+
     let now = chrono::Utc::now().timestamp();
 
+    // 根据目录中的代码检测语言，模板指定的语言优先
+    let detected = language::detect_languages(&code_dir);
+    let primary = match (&template, &detected) {
+        (Some(template), _) => template.language.clone(),
+        (None, Some(report)) => report.primary.clone(),
+        (None, None) => language::DEFAULT_LANGUAGE.to_string(),
+    };
+
     let project = Project {
+        schema_version: migration::SCHEMA_VERSION,
         id: id.clone(),
         name: name.clone(),
         description: description.clone(),
-        language: "Python".to_string(),
+        language: primary,
+        languages: detected.map(|report| report.breakdown).unwrap_or_default(),
         created_at: now,
         updated_at: now,
         root_path: root_path.clone(),
+        run_command: template.as_ref().and_then(|t| t.run_command.clone()),
+        test_command: template.as_ref().and_then(|t| t.test_command.clone()),
+        tags: Vec::new(),
+        pinned: false,
+        archived: false,
+        forked_from: None,
+        imported_from: None,
+        root_missing: false,
     };
 
     // 保存项目元数据
@@ -150,20 +259,46 @@ fn create_project(
     fs::write(&meta_file, content)
         .map_err(|e| format!("Failed to write project.json: {}", e))?;
 
+    // 记录关联目录的文件快照，目录移动后用于确认新目录
+    if root_path.is_some() {
+        if let Err(e) = relink::save_snapshot(&project_dir, &code_dir) {
+            eprintln!("{}", e);
+        }
+    }
+
     // 如果没有 root_path，创建初始需求文档
     if root_path.is_none() {
         let requirement_file = project_dir.join("requirement.md");
-        let initial_requirement = format!("# {} 需求文档\n\n## 项目描述\n{}\n\n## 功能需求\n\n## 技术栈\n\n", name, description);
-        fs::write(&requirement_file, initial_requirement)
-            .map_err(|e| format!("Failed to create requirement.md: {}", e))?;
+        let initial_requirement = match template {
+            Some(ref template) => template.render_requirement(&name, &description),
+            None => format!("# {} 需求文档\n\n## 项目描述\n{}\n\n## 功能需求\n\n## 技术栈\n\n", name, description),
+        };
+        history::write_requirement(
+            &project_dir,
+            &requirement_file,
+            &initial_requirement,
+            history::RevisionSource::Initial,
+            None,
+        )
+        .map_err(|e| format!("Failed to create requirement.md: {}", e))?;
     }
 
     Ok(project)
 }
 
+/// 重新扫描项目代码，更新主语言和语言占比
+#[tauri::command]
+fn rescan_project_languages(state: tauri::State<'_, AppState>, project_id: String) -> Result<Project, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let mut project = load_project(&project_dir)?;
+    refresh_project_languages(&project_dir, &mut project)?;
+    Ok(project)
+}
+
 #[tauri::command]
 fn delete_project(state: tauri::State<'_, AppState>, project_id: String) -> Result<(), String> {
     let project_dir = state.projects_dir.join(&project_id);
+    state.watchers.unwatch(&project_id);
 
     if project_dir.exists() {
         fs::remove_dir_all(&project_dir)
@@ -178,7 +313,7 @@ fn read_file(state: tauri::State<'_, AppState>, project_id: String, file_type: S
     let project_dir = state.projects_dir.join(&project_id);
 
     let file_path = match file_type.as_str() {
-        "requirement" => project_dir.join("requirement.md"),
+        "requirement" => requirement_path(&load_project(&project_dir)?, &project_dir),
         "chat" => project_dir.join("chat.json"),
         "tasks" => project_dir.join("tasks.json"),
         _ => return Err(format!("Unknown file type: {}", file_type)),
@@ -202,14 +337,23 @@ fn write_file(
     let project_dir = state.projects_dir.join(&project_id);
 
     let file_path = match file_type.as_str() {
-        "requirement" => project_dir.join("requirement.md"),
+        "requirement" => requirement_path(&load_project(&project_dir)?, &project_dir),
         "chat" => project_dir.join("chat.json"),
         "tasks" => project_dir.join("tasks.json"),
         _ => return Err(format!("Unknown file type: {}", file_type)),
     };
 
-    fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to write file: {}", e))
+    // 需求文档的每次修改都保存版本
+    if file_type == "requirement" {
+        history::write_requirement(&project_dir, &file_path, &content, history::RevisionSource::User, None)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    } else {
+        fs::write(&file_path, content)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+
+    touch_project(&project_dir);
+    Ok(())
 }
 
 #[tauri::command]
@@ -227,6 +371,8 @@ fn get_project_files(state: tauri::State<'_, AppState>, project_id: String) -> R
     let project: Project = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse project.json: {}", e))?;
 
+    ensure_root_exists(&project)?;
+
     // 确定要扫描的根目录
     let scan_dir = if let Some(ref root_path) = project.root_path {
         PathBuf::from(root_path)
@@ -245,50 +391,59 @@ fn get_project_files(state: tauri::State<'_, AppState>, project_id: String) -> R
     Ok(file_tree)
 }
 
+/// 分页列出项目中的一个目录，用于展开文件夹时按需加载；`path` 为空时列出根目录
+#[tauri::command]
+fn list_directory(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    path: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+) -> Result<listing::DirectoryPage, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    listing::list_dir(
+        &root,
+        path.as_deref().unwrap_or(""),
+        cursor.as_deref(),
+        limit.unwrap_or(listing::DEFAULT_PAGE_SIZE),
+    )
+}
+
 fn build_file_tree(dir: &PathBuf, base: &PathBuf) -> std::io::Result<Vec<FileNode>> {
-    // 更保守的限制，防止卡顿
-    build_file_tree_with_limit(dir, base, 0, 10, 1000)
+    // 更保守的限制，防止卡顿；文件数量限制对整棵树生效
+    let mut file_count = 0;
+    let (nodes, truncated) = build_file_tree_with_limit(dir, base, 0, 10, 1000, &mut file_count)?;
+    if truncated {
+        println!("文件树超过 {} 个文件，只列出了一部分", file_count);
+    }
+    Ok(nodes)
 }
 
-// 带限制的文件树构建，避免扫描过深或过多文件
+// 带限制的文件树构建，避免扫描过深或过多文件；返回的 bool 表示该目录没有完整列出
 fn build_file_tree_with_limit(
     dir: &PathBuf,
     base: &PathBuf,
     current_depth: u32,
     max_depth: u32,
     max_files: usize,
-) -> std::io::Result<Vec<FileNode>> {
+    file_count: &mut usize,
+) -> std::io::Result<(Vec<FileNode>, bool)> {
     let mut nodes = Vec::new();
-    let mut file_count = 0;
-
-    // 应该跳过的常见大型目录
-    let skip_dirs = [
-        "node_modules",
-        ".git",
-        "target",
-        "debug",
-        "release",
-        "build",
-        "dist",
-        ".vscode",
-        ".idea",
-        "vendor",
-        "venv",
-        ".venv",
-        "__pycache__",
-        ".next",
-        ".nuxt",
-        "coverage",
-    ];
+    let mut truncated = false;
 
     let entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => return Ok(Vec::new()), // 无权限的目录直接跳过
+        Err(_) => return Ok((Vec::new(), false)), // 无权限的目录直接跳过
     };
 
     for entry in entries {
         // 检查文件数量限制
-        if file_count >= max_files {
+        if *file_count >= max_files {
+            truncated = true;
             break;
         }
 
@@ -304,12 +459,12 @@ fn build_file_tree_with_limit(
             .to_string();
 
         // 跳过隐藏文件和目录（以.开头）
-        if name.starts_with('.') && name != ".gitignore" && name != ".env" {
+        if ignore::is_hidden(&name) {
             continue;
         }
 
         // 跳过常见的大型目录
-        if skip_dirs.contains(&name.as_str()) {
+        if ignore::is_skipped_dir(&name) {
             continue;
         }
 
@@ -320,25 +475,29 @@ fn build_file_tree_with_limit(
         if path.is_dir() {
             // 检查深度限制
             if current_depth >= max_depth {
-                // 深度超限，添加一个占位节点
+                // 深度超限，不再展开，标记为未完整列出
                 nodes.push(FileNode {
-                    name: format!("{} (深度限制)", name),
+                    name,
                     path: relative_path,
                     is_file: false,
                     children: Some(Vec::new()),
+                    truncated: true,
                 });
+                truncated = true;
                 continue;
             }
 
             // 递归扫描子目录
-            match build_file_tree_with_limit(&path, base, current_depth + 1, max_depth, max_files) {
-                Ok(children) => {
+            match build_file_tree_with_limit(&path, base, current_depth + 1, max_depth, max_files, file_count) {
+                Ok((children, child_truncated)) => {
                     nodes.push(FileNode {
                         name,
                         path: relative_path,
                         is_file: false,
                         children: Some(children),
+                        truncated: child_truncated,
                     });
+                    truncated |= child_truncated;
                 }
                 Err(_) => {
                     // 无法访问的子目录，跳过
@@ -347,12 +506,8 @@ fn build_file_tree_with_limit(
             }
         } else {
             // 跳过大型二进制文件
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_str().unwrap_or("");
-                let skip_extensions = ["dll", "exe", "so", "dylib", "bin", "pdb", "o", "a", "lib"];
-                if skip_extensions.contains(&ext_str) {
-                    continue;
-                }
+            if ignore::is_skipped_file(&path) {
+                continue;
             }
 
             nodes.push(FileNode {
@@ -360,8 +515,9 @@ fn build_file_tree_with_limit(
                 path: relative_path,
                 is_file: true,
                 children: None,
+                truncated: false,
             });
-            file_count += 1;
+            *file_count += 1;
         }
     }
 
@@ -376,7 +532,7 @@ fn build_file_tree_with_limit(
         a.name.cmp(&b.name)
     });
 
-    Ok(nodes)
+    Ok((nodes, truncated))
 }
 
 #[tauri::command]
@@ -385,6 +541,11 @@ fn get_source_file(
     project_id: String,
     relative_path: String,
 ) -> Result<String, String> {
+    // 文件树中的“需求文档”节点
+    if relative_path == "requirement" {
+        return read_file(state, project_id, relative_path);
+    }
+
     let project_dir = state.projects_dir.join(&project_id);
 
     // 读取项目元数据以确定根目录
@@ -417,6 +578,11 @@ fn save_source_file(
     relative_path: String,
     content: String,
 ) -> Result<(), String> {
+    // 文件树中的“需求文档”节点
+    if relative_path == "requirement" {
+        return write_file(state, project_id, relative_path, content);
+    }
+
     let project_dir = state.projects_dir.join(&project_id);
 
     // 读取项目元数据以确定根目录
@@ -445,7 +611,13 @@ fn save_source_file(
     }
 
     fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to write source file: {}", e))
+        .map_err(|e| format!("Failed to write source file: {}", e))?;
+
+    if let Err(e) = symbols::update_files(&project_dir, &content_dir, &[relative_path]) {
+        eprintln!("{}", e);
+    }
+    touch_project(&project_dir);
+    Ok(())
 }
 
 #[tauri::command]
@@ -478,7 +650,10 @@ fn create_file(state: tauri::State<'_, AppState>, project_id: String, relative_p
     }
 
     fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to create file: {}", e))
+        .map_err(|e| format!("Failed to create file: {}", e))?;
+
+    touch_project(&project_dir);
+    Ok(())
 }
 
 #[tauri::command]
@@ -510,7 +685,10 @@ fn create_folder(state: tauri::State<'_, AppState>, project_id: String, relative
     }
 
     fs::create_dir_all(&folder_path)
-        .map_err(|e| format!("Failed to create folder: {}", e))
+        .map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    touch_project(&project_dir);
+    Ok(())
 }
 
 #[tauri::command]
@@ -544,7 +722,10 @@ fn rename_file(state: tauri::State<'_, AppState>, project_id: String, old_path:
     }
 
     fs::rename(&old_file_path, &new_file_path)
-        .map_err(|e| format!("Failed to rename: {}", e))
+        .map_err(|e| format!("Failed to rename: {}", e))?;
+
+    touch_project(&project_dir);
+    Ok(())
 }
 
 #[tauri::command]
@@ -578,6 +759,7 @@ fn delete_file(state: tauri::State<'_, AppState>, project_id: String, relative_p
             .map_err(|e| format!("Failed to delete file: {}", e))?;
     }
 
+    touch_project(&project_dir);
     Ok(())
 }
 
@@ -632,9 +814,155 @@ fn move_file(state: tauri::State<'_, AppState>, project_id: String, source: Stri
         }
     }
 
+    touch_project(&project_dir);
     Ok(())
 }
 
+/// 生成新的项目 id
+fn new_project_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// 读取项目元数据
+fn load_project(project_dir: &Path) -> Result<Project, String> {
+    let meta_file = project_dir.join("project.json");
+    if !meta_file.exists() {
+        return Err("项目不存在".to_string());
+    }
+
+    let value = migration::load_project_json(project_dir)?;
+    serde_json::from_value(value).map_err(|e| format!("无法解析 project.json: {}", e))
+}
+
+/// 保存项目元数据
+fn save_project(project_dir: &Path, project: &Project) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(project)
+        .map_err(|e| format!("Failed to serialize project: {}", e))?;
+    fs::write(project_dir.join("project.json"), content)
+        .map_err(|e| format!("Failed to write project.json: {}", e))
+}
+
+/// 记录项目的最近活动时间，文件、文档或对话变化时调用，失败不影响原操作
+fn touch_project(project_dir: &Path) {
+    let Ok(mut project) = load_project(project_dir) else { return };
+    project.updated_at = chrono::Utc::now().timestamp();
+    if let Err(e) = save_project(project_dir, &project) {
+        eprintln!("更新项目活动时间失败: {}", e);
+    }
+}
+
+/// 重新检测项目语言并保存，没有识别到代码时保留原来的语言
+fn refresh_project_languages(project_dir: &Path, project: &mut Project) -> Result<(), String> {
+    let root = source_root(project, project_dir);
+    let Some(report) = language::detect_languages(&root) else {
+        return Ok(());
+    };
+
+    project.language = report.primary;
+    project.languages = report.breakdown;
+
+    if project.root_path.is_some() {
+        if let Err(e) = relink::save_snapshot(project_dir, &root) {
+            eprintln!("{}", e);
+        }
+    }
+    save_project(project_dir, project)
+}
+
+/// Agent 修改项目文件后的处理，同步和异步的 Agent 命令共用
+fn after_agent_edits(project_dir: &Path, project: &mut Project, task: &AgentTask, response_text: &str) {
+    // Agent 可能新增了其他语言的文件，更新语言统计
+    if let Err(e) = refresh_project_languages(project_dir, project) {
+        eprintln!("更新项目语言失败: {}", e);
+    }
+
+    // 把修改过的文件关联到提到的需求编号
+    let edited_files = trace::changed_files(&task.mtimes_before, &trace::snapshot_mtimes(&task.code_root));
+    let mentions = format!("{}\n{}", task.user_input, response_text);
+    match trace::link_agent_edits(project_dir, &task.requirement, &mentions, &edited_files) {
+        Ok(added) if added > 0 => println!("已记录 {} 条需求追踪关联", added),
+        Ok(_) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+
+    // 更新修改过的文件的符号索引
+    if let Err(e) = symbols::update_files(project_dir, &task.code_root, &edited_files) {
+        eprintln!("{}", e);
+    }
+
+    touch_project(project_dir);
+}
+
+/// 关联目录不存在时拒绝操作，提示用户重新关联
+fn ensure_root_exists(project: &Project) -> Result<(), String> {
+    match project.root_path {
+        Some(ref root_path) if !Path::new(root_path).is_dir() => Err(format!(
+            "项目关联的目录不存在: {}，请先重新关联项目目录",
+            root_path
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 需求文档位置：关联目录中的 requirement.md 或应用内的 requirement.md
+fn requirement_path(project: &Project, project_dir: &Path) -> PathBuf {
+    match project.root_path {
+        Some(ref root_path) => PathBuf::from(root_path).join("requirement.md"),
+        None => project_dir.join("requirement.md"),
+    }
+}
+
+/// 项目代码所在目录：关联目录或应用内的 src/
+fn source_root(project: &Project, project_dir: &Path) -> PathBuf {
+    if let Some(ref root_path) = project.root_path {
+        PathBuf::from(root_path)
+    } else {
+        project_dir.join("src")
+    }
+}
+
+/// 整理 AI 生成的需求文档，缺少必需章节时请 AI 补充，最多 `MAX_FIX_ATTEMPTS` 次
+async fn polish_requirement(app: &tauri::AppHandle, text: &str) -> (String, sanitize::CleanupReport) {
+    let (mut content, mut changes) = sanitize::clean(text);
+    let mut missing = sanitize::missing_sections(&content);
+    let mut fix_attempts = 0;
+
+    while !missing.is_empty() && fix_attempts < sanitize::MAX_FIX_ATTEMPTS {
+        fix_attempts += 1;
+        let _ = app.emit("agent-progress", serde_json::json!({
+            "stage": "processing",
+            "message": format!("需求文档缺少{}，正在请 AI 补充...", missing.join("、"))
+        }));
+
+        let fixed = match ask_opencode("需求文档修复", &sanitize::build_fix_prompt(&content, &missing)).await {
+            Ok(fixed) => fixed,
+            Err(e) => {
+                changes.push(format!("请 AI 补充章节失败: {}", e));
+                break;
+            }
+        };
+        let (fixed, _) = sanitize::clean(&fixed);
+        let still_missing = sanitize::missing_sections(&fixed);
+        // 修复结果没有变好时保留原来的内容
+        if still_missing.len() < missing.len() {
+            let added: Vec<&String> = missing.iter().filter(|m| !still_missing.contains(m)).collect();
+            changes.push(format!(
+                "请 AI 补充了{}",
+                added.iter().map(|m| m.as_str()).collect::<Vec<_>>().join("、")
+            ));
+            content = fixed;
+            missing = still_missing;
+        }
+    }
+
+    let report = sanitize::CleanupReport {
+        changes,
+        missing_sections: missing,
+        fix_attempts,
+    };
+    (content, report)
+}
+
 fn copy_dir_recursive(source: &PathBuf, target: &PathBuf) -> std::io::Result<()> {
     fs::create_dir_all(target)?;
     for entry in fs::read_dir(source)? {
@@ -677,13 +1005,10 @@ async fn update_requirement_with_agent(
     } else {
         return Err("项目不存在".to_string());
     };
+    ensure_root_exists(&project)?;
 
     // 2. 确定需求文档的保存位置
-    let requirement_path = if let Some(ref root_path) = project.root_path {
-        PathBuf::from(root_path).join("requirement.md")
-    } else {
-        app_project_dir.join("requirement.md")
-    };
+    let requirement_path = requirement_path(&project, &app_project_dir);
 
     let requirement_path_display = requirement_path.display().to_string();
 
@@ -787,19 +1112,21 @@ async fn update_requirement_with_agent(
 
     println!("收到响应，长度: {} 字符", response_text.len());
 
-    // 11. 保存到需求文档
-    if let Some(parent) = requirement_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("无法创建目录: {}", e))?;
-    }
+    // 11. 整理 AI 输出，缺少章节时请 AI 补充
+    let _ = client.delete_session(&session.id).await;
+    let (document, cleanup) = polish_requirement(&app, &response_text).await;
 
-    fs::write(&requirement_path, &response_text)
-        .map_err(|e| format!("无法保存需求文档: {}", e))?;
+    // 12. 保存到需求文档，同时保存版本
+    history::write_requirement(
+        &app_project_dir,
+        &requirement_path,
+        &document,
+        history::RevisionSource::Ai,
+        Some(&user_input),
+    )?;
 
     println!("需求文档已保存到: {}", requirement_path_display);
-
-    // 12. 删除临时会话
-    let _ = client.delete_session(&session.id);
+    touch_project(&app_project_dir);
 
     // 13. 发送事件通知前端
     let _ = app.emit("requirement-updated", serde_json::json!({
@@ -809,12 +1136,18 @@ async fn update_requirement_with_agent(
 
     println!("============================================");
 
+    let message = if cleanup.missing_sections.is_empty() {
+        "需求文档已更新".to_string()
+    } else {
+        format!("需求文档已更新，但仍缺少{}", cleanup.missing_sections.join("、"))
+    };
     Ok(AgentResponse {
         success: true,
-        message: "需求文档已更新".to_string(),
+        message,
         file_modified: Some(requirement_path_display),
-        document_content: Some(response_text),
+        document_content: Some(document),
         error: None,
+        cleanup: Some(cleanup),
     })
 }
 
@@ -842,6 +1175,7 @@ async fn create_files_with_agent_async(
     } else {
         return Err("项目不存在".to_string());
     };
+    ensure_root_exists(&project)?;
 
     // 2. 确定项目根目录
     let project_root = if let Some(ref root_path) = project.root_path {
@@ -853,7 +1187,7 @@ async fn create_files_with_agent_async(
     let project_root_str = project_root.display().to_string();
 
     // 3. 读取需求文档（如果存在）
-    let requirement_path = project_root.join("requirement.md");
+    let requirement_path = requirement_path(&project, &app_project_dir);
     let requirement_content = if requirement_path.exists() {
         fs::read_to_string(&requirement_path).unwrap_or_default()
     } else {
@@ -891,6 +1225,9 @@ async fn create_files_with_agent_async(
 ## 项目路径
 {}
 
+## 编程语言
+{}
+
 ## 用户需求
 {}
 
@@ -904,7 +1241,7 @@ async fn create_files_with_agent_async(
 - 确保代码可以运行
 
 请简要说明你修改了哪些文件。",
-            project_root_str, user_input
+            project_root_str, project.language, user_input
         )
     } else {
         format!(
@@ -913,6 +1250,9 @@ async fn create_files_with_agent_async(
 ## 项目路径
 {}
 
+## 编程语言
+{}
+
 ## 需求文档内容
 ```markdown{}
 ```
@@ -929,11 +1269,21 @@ async fn create_files_with_agent_async(
 - 保持代码风格一致
 - 确保代码可以运行
 
-请简要说明你修改了哪些文件。",
-            project_root_str, requirement_content, user_input
+请简要说明你修改了哪些文件，并注明实现的是需求文档中哪些编号的需求（例如 FR-1）。",
+            project_root_str, project.language, requirement_content, user_input
         )
     };
 
+    // 记录文件修改时间，完成后找出 Agent 修改过的文件
+    let code_root = source_root(&project, &app_project_dir);
+    let task = AgentTask {
+        project_id: project_id.clone(),
+        mtimes_before: trace::snapshot_mtimes(&code_root),
+        code_root,
+        requirement: requirement_content,
+        user_input: user_input.clone(),
+    };
+
     // 8. 创建会话
     println!("创建 OpenCode 会话...");
     let session = client.create_session(
@@ -952,6 +1302,7 @@ async fn create_files_with_agent_async(
         .map_err(|e| format!("发送消息失败: {}", e))?;
 
     println!("消息已异步发送，会话 ID: {}", session_id);
+    state.agent_tasks.lock().unwrap().insert(session_id.clone(), task);
 
     // 发送事件通知前端开始轮询
     let _ = app.emit("agent-task-started", serde_json::json!({
@@ -965,6 +1316,79 @@ async fn create_files_with_agent_async(
     Ok(session_id)
 }
 
+/// 异步 Agent 任务结束后由前端调用，处理 Agent 修改过的文件
+#[tauri::command]
+async fn complete_agent_task(state: tauri::State<'_, AppState>, session_id: String) -> Result<(), String> {
+    let task = state
+        .agent_tasks
+        .lock()
+        .unwrap()
+        .remove(&session_id)
+        .ok_or_else(|| format!("Agent 任务不存在或已处理: {}", session_id))?;
+
+    // Agent 的回复中可能提到实现的需求编号
+    let config = get_config();
+    let client = OpenCodeClient::new(
+        config.server_url.clone(),
+        config.username.clone(),
+        config.password.clone(),
+    );
+    let response_text = match client.get_messages(&session_id, None).await {
+        Ok(messages) => messages
+            .iter()
+            .filter(|m| m.info.role == "assistant")
+            .flat_map(|m| m.parts.iter().filter_map(|part| part.text.clone()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(e) => {
+            eprintln!("读取 Agent 回复失败: {}", e);
+            String::new()
+        }
+    };
+
+    let project_dir = state.projects_dir.join(&task.project_id);
+    let mut project = load_project(&project_dir)?;
+    after_agent_edits(&project_dir, &mut project, &task, &response_text);
+    Ok(())
+}
+
+/// 创建临时会话向 OpenCode 发送提示词，返回回复文本
+async fn ask_opencode(title: &str, prompt: &str) -> Result<String, String> {
+    let config = get_config();
+    let client = OpenCodeClient::new(
+        config.server_url.clone(),
+        config.username.clone(),
+        config.password.clone(),
+    );
+
+    client.health_check().await
+        .map_err(|e| format!("无法连接到 OpenCode Server: {}\n请检查 Server 是否运行，地址是否正确", e))?;
+
+    let session = client.create_session(
+        title,
+        config.default_provider.clone(),
+        config.default_model.clone(),
+    ).await
+        .map_err(|e| format!("创建会话失败: {}", e))?;
+
+    let result = client.send_message(&session.id, prompt, None, None).await;
+    let _ = client.delete_session(&session.id).await;
+    let response = result.map_err(|e| format!("发送消息失败: {}", e))?;
+
+    let response_text = response.parts
+        .iter()
+        .filter_map(|part| part.text.as_ref())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if response_text.trim().is_empty() {
+        return Err("AI 返回了空响应".to_string());
+    }
+
+    Ok(response_text)
+}
+
 /// 获取会话中的消息列表（用于轮询）
 #[tauri::command]
 async fn get_session_messages(session_id: String, limit: Option<u32>) -> Result<Vec<opencode::Message>, String> {
@@ -1002,6 +1426,7 @@ async fn create_files_with_agent(
     } else {
         return Err("项目不存在".to_string());
     };
+    ensure_root_exists(&project)?;
 
     // 2. 确定项目根目录
     let project_root = if let Some(ref root_path) = project.root_path {
@@ -1013,7 +1438,7 @@ async fn create_files_with_agent(
     let project_root_str = project_root.display().to_string();
 
     // 3. 读取需求文档（如果存在）
-    let requirement_path = project_root.join("requirement.md");
+    let requirement_path = requirement_path(&project, &app_project_dir);
     let requirement_content = if requirement_path.exists() {
         fs::read_to_string(&requirement_path).unwrap_or_default()
     } else {
@@ -1051,6 +1476,9 @@ async fn create_files_with_agent(
 ## 项目路径
 {}
 
+## 编程语言
+{}
+
 ## 用户需求
 {}
 
@@ -1064,7 +1492,7 @@ async fn create_files_with_agent(
 - 确保代码可以运行
 
 请简要说明你修改了哪些文件。",
-            project_root_str, user_input
+            project_root_str, project.language, user_input
         )
     } else {
         format!(
@@ -1073,6 +1501,9 @@ async fn create_files_with_agent(
 ## 项目路径
 {}
 
+## 编程语言
+{}
+
 ## 需求文档内容
 ```markdown{}
 ```
@@ -1089,11 +1520,21 @@ async fn create_files_with_agent(
 - 保持代码风格一致
 - 确保代码可以运行
 
-请简要说明你修改了哪些文件。",
-            project_root_str, requirement_content, user_input
+请简要说明你修改了哪些文件，并注明实现的是需求文档中哪些编号的需求（例如 FR-1）。",
+            project_root_str, project.language, requirement_content, user_input
         )
     };
 
+    // 记录文件修改时间，完成后找出 Agent 修改过的文件
+    let code_root = source_root(&project, &app_project_dir);
+    let task = AgentTask {
+        project_id: project_id.clone(),
+        mtimes_before: trace::snapshot_mtimes(&code_root),
+        code_root,
+        requirement: requirement_content,
+        user_input: user_input.clone(),
+    };
+
     // 8. 创建会话
     println!("创建 OpenCode 会话...");
     let session = client.create_session(
@@ -1130,7 +1571,11 @@ async fn create_files_with_agent(
     // 11. 删除临时会话
     let _ = client.delete_session(&session.id);
 
-    // 12. 发送完成事件通知前端刷新文件树
+    // 12. 处理 Agent 修改过的文件
+    let mut project = project;
+    after_agent_edits(&app_project_dir, &mut project, &task, &response_text);
+
+    // 13. 发送完成事件通知前端刷新文件树
     let _ = app.emit("files-operation-completed", serde_json::json!({
         "project_id": project_id,
         "message": response_text
@@ -1144,9 +1589,1405 @@ async fn create_files_with_agent(
         file_modified: None,
         document_content: None,
         error: None,
+        cleanup: None,
+    })
+}
+
+// ===== 导入已有代码命令 =====
+
+/// 导入已有代码目录：创建关联项目，扫描代码并生成架构概览和初始需求文档
+#[tauri::command]
+async fn import_project(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    root_path: String,
+    name: Option<String>,
+    description: Option<String>,
+) -> Result<onboarding::OnboardingResult, String> {
+    let root = PathBuf::from(&root_path);
+    if !root.is_dir() {
+        return Err(format!("目录不存在: {}", root_path));
+    }
+
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| root.file_name().and_then(|n| n.to_str()).map(|n| n.to_string()))
+        .unwrap_or_else(|| "导入的项目".to_string());
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "analyzing",
+        "message": "正在扫描项目目录..."
+    }));
+
+    let ctx = onboarding::scan_codebase(&root);
+    let description = description
+        .filter(|d| !d.trim().is_empty())
+        .or_else(|| ctx.readme_content.as_deref().and_then(onboarding::readme_summary))
+        .unwrap_or_default();
+
+    let project = new_project(&state, name, description, Some(root_path), None)?;
+    let project_dir = state.projects_dir.join(&project.id);
+
+    onboard(&app, &project_dir, project, ctx).await
+}
+
+/// 为已关联目录的项目重新生成架构概览和需求文档
+#[tauri::command]
+async fn onboard_project(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+) -> Result<onboarding::OnboardingResult, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    let ctx = onboarding::scan_codebase(&root);
+    onboard(&app, &project_dir, project, ctx).await
+}
+
+/// 请 OpenCode 生成架构概览（保存到 docs/）和描述现有功能的 requirement.md
+async fn onboard(
+    app: &tauri::AppHandle,
+    project_dir: &Path,
+    project: Project,
+    ctx: onboarding::ScanContext,
+) -> Result<onboarding::OnboardingResult, String> {
+    let root = source_root(&project, project_dir);
+    let mut warnings = Vec::new();
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": "正在生成架构概览..."
+    }));
+
+    let overview_prompt = onboarding::build_overview_prompt(&project.name, &root.display().to_string(), &ctx);
+    let overview = match ask_opencode("架构概览", &overview_prompt).await {
+        Ok(overview) => Some(overview),
+        Err(e) => {
+            warnings.push(format!("生成架构概览失败: {}", e));
+            None
+        }
+    };
+
+    let mut overview_file = None;
+    if let Some(ref overview) = overview {
+        docs::save_doc(project_dir, &root, docs::DocKind::Architecture, overview)?;
+        overview_file = Some(format!("{}/{}", docs::DOCS_DIR, onboarding::OVERVIEW_FILE));
+    }
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "working",
+        "message": "正在根据现有代码生成需求文档..."
+    }));
+
+    let requirement_prompt = onboarding::build_requirement_prompt(&project.name, &ctx, overview.as_deref());
+    let requirement = match ask_opencode("需求文档生成", &requirement_prompt).await {
+        Ok(response) => {
+            let (requirement, cleanup) = polish_requirement(app, &response).await;
+            if !cleanup.missing_sections.is_empty() {
+                warnings.push(format!("需求文档缺少{}", cleanup.missing_sections.join("、")));
+            }
+            // 导入的项目都是关联项目，需求文档保存在关联目录中，与其他读取需求文档的功能一致
+            let path = requirement_path(&project, project_dir);
+            history::write_requirement(
+                project_dir,
+                &path,
+                &requirement,
+                history::RevisionSource::Ai,
+                None,
+            )?;
+            let _ = app.emit("requirement-updated", serde_json::json!({
+                "project_id": project.id,
+                "file_path": path.display().to_string()
+            }));
+            Some(requirement)
+        }
+        Err(e) => {
+            warnings.push(format!("生成需求文档失败: {}", e));
+            None
+        }
+    };
+
+    Ok(onboarding::OnboardingResult {
+        project,
+        scan: ctx.scan,
+        overview_file,
+        requirement,
+        warnings,
     })
 }
 
+// ===== 项目文档命令 =====
+
+/// 列出项目文档及其是否过期
+#[tauri::command]
+fn list_project_docs(state: tauri::State<'_, AppState>, project_id: String) -> Result<Vec<docs::DocStatus>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    Ok(docs::list_status(&project_dir, &source_root(&project, &project_dir)))
+}
+
+/// 根据当前代码生成或刷新文档
+#[tauri::command]
+async fn generate_project_doc(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    kind: docs::DocKind,
+) -> Result<docs::GeneratedDoc, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": format!("正在生成{}...", kind.name())
+    }));
+
+    let entry_points = onboarding::find_entry_points(&root);
+    let (digest, _) = gap::build_digest(&root, &entry_points, gap::DEFAULT_TOKEN_BUDGET);
+    let previous = fs::read_to_string(project_dir.join(docs::DOCS_DIR).join(kind.file_name())).ok();
+    let prompt = docs::build_prompt(kind, &project.name, &root.display().to_string(), &digest, previous.as_deref());
+    let response_text = ask_opencode(kind.name(), &prompt).await?;
+    let (content, _) = sanitize::clean(&response_text);
+
+    docs::save_doc(&project_dir, &root, kind, &content)?;
+    touch_project(&project_dir);
+
+    let _ = app.emit("files-operation-completed", serde_json::json!({
+        "project_id": project_id,
+        "message": format!("已生成{}", kind.name())
+    }));
+
+    Ok(docs::GeneratedDoc {
+        status: docs::doc_status(&project_dir, &root, kind),
+        content,
+    })
+}
+
+// ===== 项目整理命令 =====
+
+/// 按条件筛选和排序项目
+#[tauri::command]
+fn query_projects(
+    state: tauri::State<'_, AppState>,
+    query: Option<catalog::ProjectQuery>,
+) -> Result<Vec<Project>, String> {
+    let scan = scan_projects(state)?;
+    Ok(catalog::query_projects(scan.projects, &query.unwrap_or_default()))
+}
+
+/// 所有项目使用过的标签
+#[tauri::command]
+fn list_project_tags(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let scan = scan_projects(state)?;
+    Ok(catalog::all_tags(&scan.projects))
+}
+
+/// 更新项目的标签、置顶和归档状态，未传的字段保持不变
+#[tauri::command]
+fn update_project_flags(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    tags: Option<Vec<String>>,
+    pinned: Option<bool>,
+    archived: Option<bool>,
+) -> Result<Project, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let mut project = load_project(&project_dir)?;
+
+    if let Some(tags) = tags {
+        project.tags = catalog::normalize_tags(tags);
+    }
+    if let Some(pinned) = pinned {
+        project.pinned = pinned;
+    }
+    if let Some(archived) = archived {
+        project.archived = archived;
+    }
+
+    save_project(&project_dir, &project)?;
+    Ok(project)
+}
+
+// ===== 项目复制命令 =====
+
+/// 复制项目为新项目
+///
+/// 复制元数据、需求文档、任务、文档和源码，`reset_history` 为 true 时不复制对话和任务。
+/// 指定 `target_root` 时源码复制到该目录并关联，否则复制到应用内的 src/。
+#[tauri::command]
+fn duplicate_project(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    name: Option<String>,
+    reset_history: Option<bool>,
+    target_root: Option<String>,
+) -> Result<Project, String> {
+    let source_dir = state.projects_dir.join(&project_id);
+    let source = load_project(&source_dir)?;
+    let code_root = source_root(&source, &source_dir);
+
+    let target_root = target_root.filter(|root| !root.trim().is_empty());
+    if let Some(ref root) = target_root {
+        if !fork::is_empty_dir(Path::new(root)) {
+            return Err(format!("目标目录不为空: {}", root));
+        }
+        if Path::new(root).starts_with(&code_root) {
+            return Err("目标目录不能位于原项目的代码目录中".to_string());
+        }
+    }
+
+    let id = new_project_id();
+    let project_dir = state.projects_dir.join(&id);
+    let code_dest = match target_root {
+        Some(ref root) => PathBuf::from(root),
+        None => project_dir.join("src"),
+    };
+
+    // 需求文档随原项目的布局被复制到项目目录或代码目录中，需要移到新项目的布局对应的位置
+    let copied_requirement = match source.root_path {
+        Some(_) => code_dest.join("requirement.md"),
+        None => project_dir.join("requirement.md"),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let project = Project {
+        id,
+        name: name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| format!("{} 副本", source.name)),
+        created_at: now,
+        updated_at: now,
+        root_path: target_root,
+        pinned: false,
+        archived: false,
+        forked_from: Some(source.id.clone()),
+        imported_from: None,
+        ..source
+    };
+    let requirement = requirement_path(&project, &project_dir);
+
+    let copied = fork::copy_project_data(&source_dir, &project_dir, reset_history.unwrap_or(false))
+        .and_then(|_| fork::copy_source(&code_root, &code_dest))
+        .and_then(|_| {
+            if copied_requirement != requirement && copied_requirement.is_file() {
+                fs::rename(&copied_requirement, &requirement)
+                    .map_err(|e| format!("移动需求文档失败: {}", e))?;
+            }
+            fs::create_dir_all(project_dir.join("docs"))
+                .map_err(|e| format!("Failed to create docs directory: {}", e))
+        });
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&project_dir);
+        return Err(e);
+    }
+
+    save_project(&project_dir, &project)?;
+    if project.root_path.is_some() {
+        relink::save_snapshot(&project_dir, &code_dest)?;
+    }
+
+    println!("📋 项目已复制: {} -> {}", project_id, project.id);
+    Ok(project)
+}
+
+// ===== 项目修复命令 =====
+
+/// 根据目录内容重建损坏项目的 project.json
+#[tauri::command]
+fn repair_project(state: tauri::State<'_, AppState>, project_id: String) -> Result<Project, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    if !project_dir.is_dir() {
+        return Err("项目不存在".to_string());
+    }
+
+    let project = recovery::rebuild_project(&project_dir)?;
+    println!("🔧 项目已修复: {} ({})", project.name, project.id);
+    Ok(project)
+}
+
+/// 把损坏的项目移入隔离目录，返回隔离后的路径
+#[tauri::command]
+fn quarantine_project(state: tauri::State<'_, AppState>, project_id: String) -> Result<String, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let target = recovery::quarantine(&project_dir, &state.quarantine_dir)?;
+    println!("📦 项目已隔离: {}", target);
+    Ok(target)
+}
+
+// ===== 重新关联目录命令 =====
+
+/// 检查新目录是否与项目原来关联的目录为同一份代码
+#[tauri::command]
+fn check_relink(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    new_root: String,
+) -> Result<relink::RelinkCheck, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    compare_relink_root(&project_dir, &new_root)
+}
+
+fn compare_relink_root(project_dir: &Path, new_root: &str) -> Result<relink::RelinkCheck, String> {
+    let project = load_project(project_dir)?;
+    if project.root_path.is_none() {
+        return Err("项目没有关联外部目录".to_string());
+    }
+
+    let new_root = PathBuf::from(new_root);
+    if !new_root.is_dir() {
+        return Err(format!("目录不存在: {}", new_root.display()));
+    }
+
+    Ok(relink::compare(relink::load_snapshot(project_dir).as_ref(), &new_root))
+}
+
+/// 把项目重新关联到新目录，新目录看起来不是同一份代码时需要 `force` 确认
+#[tauri::command]
+fn relink_project(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    new_root: String,
+    force: Option<bool>,
+) -> Result<Project, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let check = compare_relink_root(&project_dir, &new_root)?;
+    if !check.same_codebase && !force.unwrap_or(false) {
+        return Err(format!(
+            "新目录与原项目的文件差异较大（匹配 {} 个文件），请确认后强制关联",
+            check.matched
+        ));
+    }
+
+    let mut project = load_project(&project_dir)?;
+    project.root_path = Some(new_root.clone());
+    project.updated_at = chrono::Utc::now().timestamp();
+    save_project(&project_dir, &project)?;
+    relink::save_snapshot(&project_dir, Path::new(&new_root))?;
+    if state.watchers.is_watching(&project_id) {
+        state.watchers.watch(app, project_id.clone(), PathBuf::from(&new_root))?;
+    }
+
+    println!("🔗 项目已重新关联: {} -> {}", project.name, new_root);
+    Ok(project)
+}
+
+// ===== 项目导出导入命令 =====
+
+/// 导出项目为 zip 文件，`include_source` 为 true 时同时打包源码
+#[tauri::command]
+fn export_project(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    target_path: String,
+    include_source: Option<bool>,
+) -> Result<archive::ExportSummary, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let root = source_root(&project, &project_dir);
+    let requirement = requirement_path(&project, &project_dir);
+
+    let source = if include_source.unwrap_or(true) { Some(root.as_path()) } else { None };
+    let summary =
+        archive::export_project(&project, &project_dir, &requirement, source, Path::new(&target_path))?;
+
+    println!("📦 项目已导出: {} ({} 个文件)", summary.path, summary.files);
+    Ok(summary)
+}
+
+/// 从 zip 文件导入项目
+///
+/// id 已存在时分配新 id。指定 `target_root` 时源码解压到该目录（或在归档不含源码时直接关联该目录），
+/// 否则源码解压到应用内的 src/；归档不含源码且原关联目录在本机存在时保留原关联。
+#[tauri::command]
+fn import_project_archive(
+    state: tauri::State<'_, AppState>,
+    archive_path: String,
+    target_root: Option<String>,
+) -> Result<Project, String> {
+    let archive_path = PathBuf::from(&archive_path);
+    let manifest = archive::read_manifest(&archive_path)?;
+    let mut project = manifest.project;
+
+    // 归档中的 id 不可信，总是使用新的 id，原 id 只作为记录保存
+    let old_id = std::mem::replace(&mut project.id, new_project_id());
+    project.imported_from = Some(old_id).filter(|id| !id.is_empty());
+    let project_dir = state.projects_dir.join(&project.id);
+
+    let target_root = target_root.filter(|root| !root.trim().is_empty());
+    let new_root_path = match (target_root, &project.root_path) {
+        (Some(root), _) => Some(root),
+        (None, Some(old)) if !manifest.includes_source && Path::new(old).is_dir() => Some(old.clone()),
+        _ => None,
+    };
+
+    let source_dest = match new_root_path {
+        Some(ref root) => PathBuf::from(root),
+        None => project_dir.join("src"),
+    };
+
+    // 不覆盖已有代码
+    let extract_to_root = manifest.includes_source && new_root_path.is_some();
+    if extract_to_root && !fork::is_empty_dir(&source_dest) {
+        return Err(format!("目标目录不为空: {}", source_dest.display()));
+    }
+    let root_existed = source_dest.exists();
+
+    fs::create_dir_all(&project_dir)
+        .map_err(|e| format!("Failed to create project directory: {}", e))?;
+
+    // 需求文档按导入后的布局放置：关联目录或项目目录
+    project.root_path = new_root_path.clone();
+    let requirement_dest = requirement_path(&project, &project_dir);
+    if let Err(e) = archive::extract(&archive_path, &project_dir, &source_dest, &requirement_dest) {
+        let _ = fs::remove_dir_all(&project_dir);
+        // 目标目录在解压前是空的，清除已经写入的文件
+        if extract_to_root {
+            if root_existed {
+                if let Ok(entries) = fs::read_dir(&source_dest) {
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        let _ = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+                    }
+                }
+            } else {
+                let _ = fs::remove_dir_all(&source_dest);
+            }
+        }
+        return Err(e);
+    }
+
+    if new_root_path.is_none() {
+        for dir in ["src", "docs"] {
+            fs::create_dir_all(project_dir.join(dir))
+                .map_err(|e| format!("Failed to create {} directory: {}", dir, e))?;
+        }
+    }
+
+    project.updated_at = chrono::Utc::now().timestamp();
+    save_project(&project_dir, &project)?;
+    // 归档中记录的语言可能与解压出的代码不一致
+    if let Err(e) = refresh_project_languages(&project_dir, &mut project) {
+        eprintln!("更新项目语言失败: {}", e);
+    }
+
+    println!("📦 项目已导入: {} ({})", project.name, project.id);
+    Ok(project)
+}
+
+// ===== 需求文档版本命令 =====
+
+/// 需求文档的版本列表，最新的在前
+#[tauri::command]
+fn list_requirement_revisions(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<history::RevisionSummary>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    if !project_dir.exists() {
+        return Err("项目不存在".to_string());
+    }
+    Ok(history::list(&project_dir))
+}
+
+/// 读取某个版本的完整内容
+#[tauri::command]
+fn get_requirement_revision(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    revision_id: String,
+) -> Result<history::Revision, String> {
+    history::load(&state.projects_dir.join(&project_id), &revision_id)
+}
+
+/// 对比两个版本，`to` 为空时与当前的需求文档对比
+#[tauri::command]
+fn diff_requirement_revisions(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    from: String,
+    to: Option<String>,
+) -> Result<history::RevisionDiff, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let old = history::load(&project_dir, &from)?;
+
+    let (new_content, to_label) = match to {
+        Some(to) => (history::load(&project_dir, &to)?.content, to),
+        None => {
+            let project = load_project(&project_dir)?;
+            let current = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+            (current, "current".to_string())
+        }
+    };
+
+    Ok(history::diff(&old.content, &new_content, &from, &to_label))
+}
+
+/// 把需求文档恢复到某个版本，恢复本身也保存为一个新版本
+#[tauri::command]
+fn restore_requirement_revision(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    revision_id: String,
+) -> Result<String, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let revision = history::load(&project_dir, &revision_id)?;
+    let path = requirement_path(&project, &project_dir);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    fs::write(&path, &revision.content).map_err(|e| format!("无法保存需求文档: {}", e))?;
+    history::record(
+        &project_dir,
+        &revision.content,
+        history::RevisionSource::Restore,
+        None,
+        Some(&revision_id),
+    )?;
+    touch_project(&project_dir);
+
+    let _ = app.emit("requirement-updated", serde_json::json!({
+        "project_id": project_id,
+        "file_path": path.display().to_string()
+    }));
+
+    Ok(revision.content)
+}
+
+// ===== 需求条目命令 =====
+
+/// 读取需求文档的结构化模型
+#[tauri::command]
+fn get_requirement_model(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+) -> Result<requirement::RequirementModel, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let content = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+    Ok(requirement::RequirementDoc::parse(&content).model())
+}
+
+/// 解析需求文档、执行修改并写回，修改保存为用户编辑的版本
+fn edit_requirement(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    project_id: &str,
+    edit: impl FnOnce(&mut requirement::RequirementDoc) -> Result<(), String>,
+) -> Result<requirement::RequirementModel, String> {
+    let project_dir = state.projects_dir.join(project_id);
+    let project = load_project(&project_dir)?;
+    let path = requirement_path(&project, &project_dir);
+
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let mut doc = requirement::RequirementDoc::parse(&content);
+    edit(&mut doc)?;
+
+    history::write_requirement(&project_dir, &path, &doc.render(), history::RevisionSource::User, None)?;
+    touch_project(&project_dir);
+
+    let _ = app.emit("requirement-updated", serde_json::json!({
+        "project_id": project_id,
+        "file_path": path.display().to_string()
+    }));
+
+    Ok(doc.model())
+}
+
+/// 添加一条需求，`after` 为空时添加到章节末尾
+#[tauri::command]
+fn add_requirement_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    section: requirement::SectionKind,
+    text: String,
+    after: Option<String>,
+) -> Result<requirement::RequirementModel, String> {
+    edit_requirement(&app, &state, &project_id, |doc| {
+        doc.add_item(section, &text, after.as_deref()).map(|_| ())
+    })
+}
+
+/// 修改一条需求的内容
+#[tauri::command]
+fn update_requirement_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    item_id: String,
+    text: String,
+) -> Result<requirement::RequirementModel, String> {
+    edit_requirement(&app, &state, &project_id, |doc| doc.update_item(&item_id, &text))
+}
+
+/// 删除一条需求
+#[tauri::command]
+fn remove_requirement_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    item_id: String,
+) -> Result<requirement::RequirementModel, String> {
+    edit_requirement(&app, &state, &project_id, |doc| doc.remove_item(&item_id))
+}
+
+/// 把一条需求移动到所在章节的第 `index` 个位置（从 0 开始）
+#[tauri::command]
+fn move_requirement_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    item_id: String,
+    index: usize,
+) -> Result<requirement::RequirementModel, String> {
+    edit_requirement(&app, &state, &project_id, |doc| doc.move_item(&item_id, index))
+}
+
+// ===== 需求追踪命令 =====
+
+/// 读取全部需求追踪关联
+#[tauri::command]
+fn list_trace_links(state: tauri::State<'_, AppState>, project_id: String) -> Result<Vec<trace::TraceLink>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    if !project_dir.exists() {
+        return Err("项目不存在".to_string());
+    }
+    Ok(trace::load_store(&project_dir).links)
+}
+
+/// 手动把需求关联到文件和任务
+#[tauri::command]
+fn link_requirement(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    requirement_id: String,
+    files: Option<Vec<String>>,
+    tasks: Option<Vec<String>>,
+) -> Result<Vec<trace::TraceLink>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let content = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+    let exists = trace::traceable_items(&requirement::RequirementDoc::parse(&content))
+        .iter()
+        .any(|item| item.id == requirement_id);
+    if !exists {
+        return Err(format!("需求条目不存在: {}", requirement_id));
+    }
+
+    let mut store = trace::load_store(&project_dir);
+    store.add(&requirement_id, trace::LinkKind::File, &files.unwrap_or_default(), trace::LinkSource::Manual);
+    store.add(&requirement_id, trace::LinkKind::Task, &tasks.unwrap_or_default(), trace::LinkSource::Manual);
+    trace::save_store(&project_dir, &store)?;
+
+    Ok(store.links.into_iter().filter(|l| l.requirement_id == requirement_id).collect())
+}
+
+/// 删除一条需求追踪关联
+#[tauri::command]
+fn unlink_requirement(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    requirement_id: String,
+    kind: trace::LinkKind,
+    target: String,
+) -> Result<(), String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let mut store = trace::load_store(&project_dir);
+    if !store.remove(&requirement_id, kind, &target) {
+        return Err("关联不存在".to_string());
+    }
+    trace::save_store(&project_dir, &store)
+}
+
+/// 生成需求覆盖报告
+#[tauri::command]
+fn get_coverage_report(state: tauri::State<'_, AppState>, project_id: String) -> Result<trace::CoverageReport, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+
+    let root = source_root(&project, &project_dir);
+    Ok(trace::coverage_report(&project_dir, &root, &requirement_path(&project, &project_dir)))
+}
+
+// ===== 需求差距分析命令 =====
+
+/// 对比需求文档和代码，分析每条需求的实现情况
+#[tauri::command]
+async fn analyze_requirement_gaps(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    token_budget: Option<usize>,
+) -> Result<gap::GapReport, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    let requirement = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+    if requirement.trim().is_empty() {
+        return Err("需求文档为空，无法分析".to_string());
+    }
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": "正在整理代码摘要..."
+    }));
+    let entry_points = onboarding::find_entry_points(&root);
+    let (digest, summary) = gap::build_digest(&root, &entry_points, token_budget.unwrap_or(gap::DEFAULT_TOKEN_BUDGET));
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "working",
+        "message": "正在对比需求文档和代码..."
+    }));
+    let response_text = ask_opencode("需求差距分析", &gap::build_prompt(&requirement, &digest)).await?;
+    let report = gap::parse_report(&testing::extract_code_block(&response_text), &requirement, &root, summary)?;
+    gap::save_report(&project_dir, &report)?;
+
+    Ok(report)
+}
+
+/// 读取最近一次的需求差距分析结果
+#[tauri::command]
+fn get_gap_report(state: tauri::State<'_, AppState>, project_id: String) -> Result<Option<gap::GapReport>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    if !project_dir.exists() {
+        return Err("项目不存在".to_string());
+    }
+    Ok(gap::load_report(&project_dir))
+}
+
+// ===== 需求访谈命令 =====
+
+/// 读取项目的需求访谈，用于应用重启后继续
+#[tauri::command]
+fn get_interview(state: tauri::State<'_, AppState>, project_id: String) -> Result<Option<interview::InterviewSession>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    if !project_dir.exists() {
+        return Err("项目不存在".to_string());
+    }
+    Ok(interview::load(&project_dir))
+}
+
+/// 让 AI 提出下一个问题并保存访谈
+async fn ask_next_interview_question(
+    app: &tauri::AppHandle,
+    project: &Project,
+    project_dir: &Path,
+    session: &mut interview::InterviewSession,
+) -> Result<(), String> {
+    if session.turns.len() < interview::MAX_QUESTIONS {
+        let _ = app.emit("agent-progress", serde_json::json!({
+            "stage": "processing",
+            "message": "正在准备下一个问题..."
+        }));
+        let reply = ask_opencode("需求访谈", &interview::build_question_prompt(&project.name, session)).await?;
+        session.advance(interview::parse_next_step(&reply, session));
+    } else {
+        session.advance(interview::NextStep::Done);
+    }
+    interview::save(project_dir, session)
+}
+
+/// 开始需求访谈
+///
+/// 已有未完成的访谈时继续该访谈（缺少待回答的问题时重新提问），`restart` 为 true 时重新开始。
+#[tauri::command]
+async fn start_interview(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    idea: String,
+    restart: Option<bool>,
+) -> Result<interview::InterviewSession, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+
+    let existing = interview::load(&project_dir)
+        .filter(|s| s.status != interview::InterviewStatus::Completed && !restart.unwrap_or(false));
+    let mut session = match existing {
+        Some(session) => session,
+        None => {
+            if idea.trim().is_empty() {
+                return Err("请先简单描述你想做的程序".to_string());
+            }
+            interview::InterviewSession::new(&idea)
+        }
+    };
+
+    if session.status == interview::InterviewStatus::Asking && session.pending().is_none() {
+        interview::save(&project_dir, &session)?;
+        ask_next_interview_question(&app, &project, &project_dir, &mut session).await?;
+    }
+    Ok(session)
+}
+
+/// 回答当前问题并获取下一个问题，问题问完时访谈进入生成文档阶段
+#[tauri::command]
+async fn answer_interview(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    answer: String,
+) -> Result<interview::InterviewSession, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let mut session = interview::load(&project_dir).ok_or_else(|| "没有进行中的需求访谈".to_string())?;
+
+    // 先保存回答，提问失败时回答不会丢失
+    session.answer(&answer)?;
+    interview::save(&project_dir, &session)?;
+
+    ask_next_interview_question(&app, &project, &project_dir, &mut session).await?;
+    Ok(session)
+}
+
+/// 根据访谈记录生成需求文档，访谈记录保存在需求文档旁边
+#[tauri::command]
+async fn finish_interview(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+) -> Result<AgentResponse, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let mut session = interview::load(&project_dir).ok_or_else(|| "没有进行中的需求访谈".to_string())?;
+    if session.status == interview::InterviewStatus::Completed {
+        return Err("需求访谈已经完成".to_string());
+    }
+    if !session.turns.iter().any(|t| t.answer.is_some()) {
+        return Err("请至少回答一个问题".to_string());
+    }
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": "正在根据访谈生成需求文档..."
+    }));
+    let prompt = interview::build_requirement_prompt(&project.name, &project.language, &session);
+    let response = ask_opencode("需求访谈", &prompt).await?;
+    let (content, cleanup) = polish_requirement(&app, &response).await;
+
+    let path = requirement_path(&project, &project_dir);
+    history::write_requirement(
+        &project_dir,
+        &path,
+        &content,
+        history::RevisionSource::Ai,
+        Some(&format!("需求访谈：{}", session.idea)),
+    )?;
+
+    // 未回答的问题不计入访谈
+    session.turns.retain(|t| t.answer.is_some());
+    session.status = interview::InterviewStatus::Completed;
+    session.updated_at = chrono::Utc::now().timestamp();
+    interview::save(&project_dir, &session)?;
+    if let Some(dir) = path.parent() {
+        fs::write(dir.join(interview::TRANSCRIPT_FILE), interview::render_transcript(&session))
+            .map_err(|e| format!("无法保存访谈记录: {}", e))?;
+    }
+    touch_project(&project_dir);
+
+    let path_display = path.display().to_string();
+    let _ = app.emit("requirement-updated", serde_json::json!({
+        "project_id": project_id,
+        "file_path": path_display
+    }));
+
+    Ok(AgentResponse {
+        success: true,
+        message: "需求文档已根据访谈生成".to_string(),
+        file_modified: Some(path_display),
+        document_content: Some(content),
+        error: None,
+        cleanup: Some(cleanup),
+    })
+}
+
+/// 放弃进行中的需求访谈
+#[tauri::command]
+fn cancel_interview(state: tauri::State<'_, AppState>, project_id: String) -> Result<(), String> {
+    interview::remove(&state.projects_dir.join(&project_id))
+}
+
+// ===== 项目搜索与替换命令 =====
+
+/// 在项目目录中搜索，立即返回 search id，结果通过 search-result 和 search-finished 事件推送
+#[tauri::command]
+fn search_project(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    options: search::SearchOptions,
+) -> Result<String, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    search::start_search(app, &state.searches, project_id, root, &options)
+}
+
+/// 取消进行中的搜索
+#[tauri::command]
+fn cancel_search(state: tauri::State<'_, AppState>, search_id: String) -> Result<(), String> {
+    state.searches.cancel(&search_id)
+}
+
+/// 预览项目范围的替换
+#[tauri::command]
+fn preview_replace(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    options: replace::ReplaceOptions,
+) -> Result<replace::ReplacePreview, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    replace::preview(&source_root(&project, &project_dir), &options)
+}
+
+/// 按用户选择的文件和匹配应用替换，有文件在预览后被修改时不写入任何文件
+#[tauri::command]
+fn apply_replace(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    options: replace::ReplaceOptions,
+    selections: Vec<replace::FileSelection>,
+) -> Result<replace::ReplaceResult, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+
+    let root = source_root(&project, &project_dir);
+    let result = replace::apply(&root, &options, &selections)?;
+    if result.applied && !result.changed_files.is_empty() {
+        if let Err(e) = symbols::update_files(&project_dir, &root, &result.changed_files) {
+            eprintln!("{}", e);
+        }
+        touch_project(&project_dir);
+        let _ = app.emit("files-operation-completed", serde_json::json!({
+            "project_id": project_id,
+            "message": format!("已在 {} 个文件中替换 {} 处", result.changed_files.len(), result.replacements)
+        }));
+    }
+    Ok(result)
+}
+
+// ===== 代码符号命令 =====
+
+/// 读取文件的大纲（函数、类和方法），不支持的语言返回空列表
+#[tauri::command]
+fn get_file_outline(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<Vec<symbols::Symbol>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let content = fs::read_to_string(source_root(&project, &project_dir).join(&relative_path))
+        .map_err(|e| format!("无法读取文件: {}", e))?;
+    Ok(symbols::extract(&relative_path, &content).unwrap_or_default())
+}
+
+/// 在项目中按名称查找符号，用于跳转到符号；会先增量更新符号索引
+#[tauri::command]
+async fn search_symbols(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<symbols::SymbolMatch>, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    tokio::task::spawn_blocking(move || {
+        let index = symbols::refresh(&project_dir, &root);
+        symbols::find(&index, &query, limit.unwrap_or(50))
+    })
+    .await
+    .map_err(|e| format!("符号查找失败: {}", e))
+}
+
+// ===== 文件监听命令 =====
+
+/// 打开项目时开始监听项目目录，修改通过 project-files-changed 事件推送
+#[tauri::command]
+fn watch_project(app: tauri::AppHandle, state: tauri::State<'_, AppState>, project_id: String) -> Result<(), String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+    fs::create_dir_all(&root).map_err(|e| format!("无法创建源码目录: {}", e))?;
+
+    state.watchers.watch(app, project_id, root)
+}
+
+/// 关闭项目时停止监听
+#[tauri::command]
+fn unwatch_project(state: tauri::State<'_, AppState>, project_id: String) {
+    state.watchers.unwatch(&project_id);
+}
+
+// ===== 项目模板命令 =====
+
+/// 列出可用的项目模板，指定语言时该语言的模板排在前面
+#[tauri::command]
+fn list_templates(state: tauri::State<'_, AppState>, language: Option<String>) -> Vec<templates::TemplateSummary> {
+    templates::list_templates(&state.templates_dir, language.as_deref())
+}
+
+/// 将已有项目保存为新模板
+#[tauri::command]
+fn save_project_as_template(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    name: String,
+    description: String,
+) -> Result<templates::TemplateSummary, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let root = source_root(&project, &project_dir);
+    let requirement = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+
+    let template = templates::save_template(
+        &state.templates_dir,
+        &project,
+        &root,
+        &requirement,
+        name,
+        description,
+    )?;
+    Ok(template.summary())
+}
+
+/// 删除用户保存的模板
+#[tauri::command]
+fn delete_template(state: tauri::State<'_, AppState>, template_id: String) -> Result<(), String> {
+    templates::delete_template(&state.templates_dir, &template_id)
+}
+
+// ===== 代码运行命令 =====
+
+/// 运行项目入口或指定文件，返回 run id；输出通过 `run-output` / `run-exited` 事件推送
+#[tauri::command]
+async fn run_project(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    relative_path: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<String, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    if !root.exists() {
+        return Err(format!("项目目录不存在: {}", root.display()));
+    }
+
+    let command = runner::resolve_command(
+        &root,
+        &project.language,
+        project.run_command.as_deref(),
+        relative_path.as_deref(),
+    )?;
+    println!("▶ 运行: {} (目录: {})", command.display(), root.display());
+
+    runner::start_run(app, &state.runner, project_id, root, command, timeout_secs)
+}
+
+/// 向运行中的程序写入标准输入，`close` 为 true 时发送 EOF
+#[tauri::command]
+fn write_run_stdin(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+    input: String,
+    close: Option<bool>,
+) -> Result<(), String> {
+    if !input.is_empty() {
+        state.runner.write_stdin(&run_id, Some(input))?;
+    }
+    if close.unwrap_or(false) {
+        state.runner.write_stdin(&run_id, None)?;
+    }
+    Ok(())
+}
+
+/// 终止运行中的程序
+#[tauri::command]
+fn kill_run(state: tauri::State<'_, AppState>, run_id: String) -> Result<(), String> {
+    state.runner.kill(&run_id)
+}
+
+/// 解释运行失败的原因：解析错误输出、定位项目文件并请 OpenCode 给出初学者能理解的说明
+///
+/// 指定 `output` 时直接解释这段输出；否则使用 `run_id` 对应（或项目最近一次）的运行记录。
+#[tauri::command]
+async fn explain_run_error(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    run_id: Option<String>,
+    output: Option<String>,
+) -> Result<diagnostics::ErrorExplanation, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    let root = source_root(&project, &project_dir);
+
+    let (run_id, output) = match output {
+        Some(output) => (run_id, output),
+        None => {
+            let record = match run_id {
+                Some(ref id) => state.runner.record(id),
+                None => state.runner.latest_record(&project_id),
+            }
+            .ok_or_else(|| "没有找到可以解释的运行记录".to_string())?;
+
+            if record.exit.success {
+                return Err("程序运行成功，没有需要解释的错误".to_string());
+            }
+            let output = diagnostics::error_output(&record.stderr, &record.stdout).to_string();
+            if output.trim().is_empty() {
+                return Err(if record.output_incomplete {
+                    "读取程序输出超时（可能有后台进程仍占用输出），没有获取到错误信息".to_string()
+                } else {
+                    "程序没有输出错误信息".to_string()
+                });
+            }
+            (Some(record.exit.run_id), output)
+        }
+    };
+
+    let mut parsed = diagnostics::parse_error_output(&output);
+
+    // 文本形式的编译错误信息有限，重新以 JSON 格式编译获取完整诊断
+    let is_rust_compile_error = output.contains("could not compile")
+        && parsed.as_ref().map(|p| p.kind == "rust").unwrap_or(true);
+    if is_rust_compile_error && root.join("Cargo.toml").exists() {
+        let build = tokio::process::Command::new("cargo")
+            .args(["build", "--quiet", "--message-format=json"])
+            .current_dir(&root)
+            .output()
+            .await;
+        if let Ok(build) = build {
+            let json_output = String::from_utf8_lossy(&build.stdout);
+            if let Some(json_parsed) = diagnostics::parse_rust_json(&json_output) {
+                parsed = Some(json_parsed);
+            }
+        }
+    }
+
+    let mut parsed = parsed.unwrap_or_else(|| diagnostics::fallback_error(&output));
+    diagnostics::resolve_locations(&mut parsed, &root);
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": "正在分析错误原因..."
+    }));
+
+    let prompt = diagnostics::build_explain_prompt(&parsed, &output, &project.language);
+    let explanation = ask_opencode("错误解释", &prompt).await?;
+
+    Ok(diagnostics::ErrorExplanation {
+        run_id,
+        kind: parsed.kind,
+        message: parsed.message,
+        locations: parsed.locations,
+        explanation,
+    })
+}
+
+/// 列出正在运行的程序
+#[tauri::command]
+fn list_runs(state: tauri::State<'_, AppState>) -> Vec<String> {
+    state.runner.running()
+}
+
+// ===== 测试命令 =====
+
+/// 为指定源文件生成单元测试，写入惯用位置后运行并返回每个用例的结果
+#[tauri::command]
+async fn generate_tests(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<testing::GeneratedTests, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    let relative_path = relative_path.replace('\\', "/");
+    let framework = testing::detect_framework(&root, Some(&relative_path))?;
+    let test_file = testing::test_file_path(framework, &relative_path)?;
+    let source = fs::read_to_string(root.join(&relative_path))
+        .map_err(|e| format!("无法读取源文件: {}", e))?;
+
+    // 单独的测试文件可能已被修改过，不覆盖（Rust 测试只替换之前生成的测试模块）
+    let test_path = root.join(&test_file);
+    if framework != testing::TestFramework::Cargo && test_path.exists() {
+        return Err(format!("测试文件 {} 已存在，为避免覆盖其中的修改，请先重命名或删除它", test_file));
+    }
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": format!("正在生成 {} 测试...", framework.name())
+    }));
+
+    let prompt = testing::build_generate_prompt(framework, &relative_path, &source, &test_file);
+    let response_text = ask_opencode("测试生成", &prompt).await?;
+    let generated = testing::extract_code_block(&response_text);
+
+    let content = match framework {
+        testing::TestFramework::Cargo => testing::merge_rust_tests(&source, &generated),
+        _ => format!("{}\n", generated.trim_end()),
+    };
+
+    if let Some(parent) = test_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    fs::write(&test_path, &content)
+        .map_err(|e| format!("无法保存测试文件: {}", e))?;
+
+    println!("测试已写入: {}", test_path.display());
+    touch_project(&project_dir);
+
+    let _ = app.emit("files-operation-completed", serde_json::json!({
+        "project_id": project_id,
+        "message": format!("已生成测试文件 {}", test_file)
+    }));
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "working",
+        "message": "正在运行测试..."
+    }));
+
+    let result = testing::run_tests(&root, framework, Some(&test_file), None).await?;
+
+    Ok(testing::GeneratedTests {
+        framework,
+        test_file,
+        content,
+        result,
+    })
+}
+
+/// 根据需求文档中的验收标准生成 Gherkin 场景和测试骨架，并运行测试
+#[tauri::command]
+async fn generate_acceptance_tests(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+) -> Result<acceptance::AcceptanceScaffold, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    let content = fs::read_to_string(requirement_path(&project, &project_dir)).unwrap_or_default();
+    let model = requirement::RequirementDoc::parse(&content).model();
+    if model.acceptance.is_empty() {
+        return Err("需求文档中没有验收标准，请先在「验收标准」章节中添加条目".to_string());
+    }
+    let framework = testing::detect_framework(&root, None)?;
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "processing",
+        "message": "正在根据验收标准编写场景..."
+    }));
+
+    let prompt = acceptance::build_scenario_prompt(&project.name, &model.description, &model.acceptance);
+    let response_text = ask_opencode("验收场景生成", &prompt).await?;
+    let scenarios = acceptance::parse_feature(&testing::extract_code_block(&response_text), &model.acceptance);
+
+    let feature_path = root.join(acceptance::FEATURE_FILE);
+    let test_file = acceptance::test_file_path(framework).to_string();
+    let test_path = root.join(&test_file);
+    let existing = fs::read_to_string(&test_path).ok();
+    let (test_content, added) = acceptance::merge_skeletons(existing.as_deref(), framework, &scenarios);
+    let existing_feature = fs::read_to_string(&feature_path).ok();
+    let feature_content = acceptance::merge_feature(existing_feature.as_deref(), &project.name, &scenarios);
+
+    for path in [&feature_path, &test_path] {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+    }
+    fs::write(&feature_path, feature_content)
+        .map_err(|e| format!("无法保存场景文件: {}", e))?;
+    fs::write(&test_path, &test_content)
+        .map_err(|e| format!("无法保存测试文件: {}", e))?;
+
+    // 把测试文件关联到对应的验收标准
+    let mut store = trace::load_store(&project_dir);
+    let files = [test_file.clone(), acceptance::FEATURE_FILE.to_string()];
+    for item in &model.acceptance {
+        store.add(&item.id, trace::LinkKind::File, &files, trace::LinkSource::Agent);
+    }
+    trace::save_store(&project_dir, &store)?;
+    touch_project(&project_dir);
+
+    let _ = app.emit("files-operation-completed", serde_json::json!({
+        "project_id": project_id,
+        "message": format!("已生成验收测试 {}", test_file)
+    }));
+
+    let _ = app.emit("agent-progress", serde_json::json!({
+        "stage": "working",
+        "message": "正在运行验收测试..."
+    }));
+
+    let result = testing::run_tests(&root, framework, Some(&test_file), None).await?;
+
+    Ok(acceptance::AcceptanceScaffold {
+        framework,
+        feature_file: acceptance::FEATURE_FILE.to_string(),
+        test_file,
+        scenarios,
+        added,
+        result,
+    })
+}
+
+/// 运行项目测试，`test_file` 为空时运行全部测试
+#[tauri::command]
+async fn run_project_tests(
+    state: tauri::State<'_, AppState>,
+    project_id: String,
+    test_file: Option<String>,
+) -> Result<testing::TestRunResult, String> {
+    let project_dir = state.projects_dir.join(&project_id);
+    let project = load_project(&project_dir)?;
+    ensure_root_exists(&project)?;
+    let root = source_root(&project, &project_dir);
+
+    // 运行全部测试时使用项目配置的测试命令
+    let test_command = project.test_command.as_deref().filter(|_| test_file.is_none());
+    let framework = match test_command.and_then(testing::TestFramework::from_command) {
+        Some(framework) => framework,
+        None => testing::detect_framework(&root, test_file.as_deref())?,
+    };
+    testing::run_tests(&root, framework, test_file.as_deref(), test_command).await
+}
+
 // ===== OpenCode 配置命令 =====
 
 /// 获取 OpenCode 配置
@@ -1281,6 +3122,12 @@ fn main() {
 
             app.manage(AppState {
                 projects_dir,
+                templates_dir: app_data_dir.join("templates"),
+                quarantine_dir: app_data_dir.join("quarantine"),
+                runner: runner::RunnerRegistry::default(),
+                searches: search::SearchRegistry::default(),
+                watchers: watcher::WatcherRegistry::default(),
+                agent_tasks: Arc::default(),
             });
 
             println!("🚀 Code Sensei 已启动");
@@ -1291,10 +3138,30 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             scan_projects,
             create_project,
+            rescan_project_languages,
             delete_project,
+            query_projects,
+            list_project_tags,
+            update_project_flags,
+            duplicate_project,
+            repair_project,
+            quarantine_project,
+            check_relink,
+            relink_project,
+            list_templates,
+            save_project_as_template,
+            delete_template,
+            import_project,
+            onboard_project,
+            // 项目文档命令
+            list_project_docs,
+            generate_project_doc,
+            export_project,
+            import_project_archive,
             read_file,
             write_file,
             get_project_files,
+            list_directory,
             get_source_file,
             save_source_file,
             create_file,
@@ -1305,7 +3172,54 @@ fn main() {
             update_requirement_with_agent,
             create_files_with_agent,
             create_files_with_agent_async,
+            complete_agent_task,
             get_session_messages,
+            // 需求文档版本命令
+            list_requirement_revisions,
+            get_requirement_revision,
+            diff_requirement_revisions,
+            restore_requirement_revision,
+            // 需求条目命令
+            get_requirement_model,
+            add_requirement_item,
+            update_requirement_item,
+            remove_requirement_item,
+            move_requirement_item,
+            // 需求追踪命令
+            list_trace_links,
+            link_requirement,
+            unlink_requirement,
+            get_coverage_report,
+            // 需求差距分析命令
+            analyze_requirement_gaps,
+            get_gap_report,
+            // 需求访谈命令
+            get_interview,
+            start_interview,
+            answer_interview,
+            finish_interview,
+            cancel_interview,
+            // 项目搜索与替换命令
+            search_project,
+            cancel_search,
+            preview_replace,
+            apply_replace,
+            // 代码符号命令
+            get_file_outline,
+            search_symbols,
+            // 文件监听命令
+            watch_project,
+            unwatch_project,
+            // 代码运行命令
+            run_project,
+            write_run_stdin,
+            kill_run,
+            list_runs,
+            explain_run_error,
+            // 测试命令
+            generate_tests,
+            generate_acceptance_tests,
+            run_project_tests,
             // OpenCode 配置命令
             get_opencode_config,
             save_opencode_config,
@@ -0,0 +1,147 @@
+// 项目元数据迁移：读取 project.json 时把旧版本的数据逐步升级到当前结构
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// 当前 project.json 的结构版本，没有 schema_version 字段的文件视为版本 0
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 迁移步骤，下标 i 的函数把版本 i 升级到版本 i + 1
+const MIGRATIONS: [fn(&mut Value, &str); SCHEMA_VERSION as usize] = [migrate_v0_to_v1];
+
+// ===== 迁移步骤 =====
+
+/// 版本 0：最早的 project.json，id 为时间戳，部分字段可能缺失
+fn migrate_v0_to_v1(project: &mut Value, dir_name: &str) {
+    let now = chrono::Utc::now().timestamp();
+
+    // id 必须与目录名一致，早期手工复制的项目可能不一致或写成了数字
+    project["id"] = json!(dir_name);
+
+    if !project["name"].is_string() {
+        project["name"] = json!(dir_name);
+    }
+    if !project["description"].is_string() {
+        project["description"] = json!("");
+    }
+    if !project["language"].is_string() {
+        project["language"] = json!(crate::language::DEFAULT_LANGUAGE);
+    }
+    if !project["created_at"].is_i64() {
+        project["created_at"] = json!(now);
+    }
+    if !project["updated_at"].is_i64() {
+        let created_at = project["created_at"].clone();
+        project["updated_at"] = created_at;
+    }
+}
+
+// ===== 迁移流程 =====
+
+/// 读取结构版本
+pub fn schema_version(project: &Value) -> u32 {
+    project["schema_version"].as_u64().unwrap_or(0) as u32
+}
+
+/// 把项目元数据升级到当前版本，返回升级前的版本；已是最新版本时返回 None
+pub fn migrate(project: &mut Value, dir_name: &str) -> Result<Option<u32>, String> {
+    if !project.is_object() {
+        return Err("project.json 不是有效的项目数据".to_string());
+    }
+
+    let from = schema_version(project);
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "项目数据版本 {} 过新，请升级 Code Sensei 后再打开",
+            from
+        ));
+    }
+    if from == SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    for step in &MIGRATIONS[from as usize..] {
+        step(project, dir_name);
+    }
+    project["schema_version"] = json!(SCHEMA_VERSION);
+    Ok(Some(from))
+}
+
+/// 读取项目目录中的 project.json，需要时迁移并写回
+///
+/// 写回前把原文件备份为 project.json.v<旧版本>.bak，已有同名备份时不覆盖。
+pub fn load_project_json(project_dir: &Path) -> Result<Value, String> {
+    let meta_file = project_dir.join("project.json");
+    let content = fs::read_to_string(&meta_file)
+        .map_err(|e| format!("无法读取 project.json: {}", e))?;
+    let mut project: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("无法解析 project.json: {}", e))?;
+
+    let dir_name = project_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let Some(from) = migrate(&mut project, &dir_name)? else {
+        return Ok(project);
+    };
+
+    let backup = project_dir.join(format!("project.json.v{}.bak", from));
+    if !backup.exists() {
+        fs::write(&backup, &content).map_err(|e| format!("无法备份 project.json: {}", e))?;
+    }
+
+    let upgraded = serde_json::to_string_pretty(&project)
+        .map_err(|e| format!("无法序列化 project.json: {}", e))?;
+    fs::write(&meta_file, upgraded).map_err(|e| format!("无法写入 project.json: {}", e))?;
+
+    println!("🔄 项目数据已从版本 {} 升级到 {}: {}", from, SCHEMA_VERSION, dir_name);
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v0() {
+        let mut project = json!({
+            "id": 1700000000000i64,
+            "name": "demo",
+            "created_at": 1700000000
+        });
+
+        assert_eq!(migrate(&mut project, "1700000000000").unwrap(), Some(0));
+        assert_eq!(project["id"], "1700000000000");
+        assert_eq!(project["description"], "");
+        assert_eq!(project["language"], "Python");
+        assert_eq!(project["updated_at"], 1700000000);
+        assert_eq!(project["schema_version"], SCHEMA_VERSION);
+
+        assert_eq!(migrate(&mut project, "1700000000000").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let mut project = json!({ "schema_version": SCHEMA_VERSION + 1 });
+        assert!(migrate(&mut project, "x").is_err());
+    }
+
+    #[test]
+    fn test_load_project_json_keeps_backup() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let original = r#"{"id":"old","name":"demo","description":"","language":"Rust","created_at":1,"updated_at":2}"#;
+        fs::write(dir.join("project.json"), original).unwrap();
+
+        let project = load_project_json(&dir).unwrap();
+        let backup = fs::read_to_string(dir.join("project.json.v0.bak")).unwrap();
+        let rewritten: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("project.json")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(project["language"], "Rust");
+        assert_eq!(backup, original);
+        assert_eq!(rewritten["schema_version"], SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,351 @@
+// 导入已有代码：扫描目录、识别入口和说明文档，并构建生成架构概览和需求文档的提示词
+use crate::ignore;
+use crate::language::{self, LanguageReport};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 提示词中最多列出的文件数
+const MAX_LISTED_FILES: usize = 300;
+
+/// README 最多读取的字节数
+const README_LIMIT: usize = 8 * 1024;
+
+/// 每个清单文件最多读取的字节数
+const MANIFEST_LIMIT: usize = 4 * 1024;
+
+/// 每个入口文件最多读取的字节数
+const ENTRY_LIMIT: usize = 4 * 1024;
+
+/// 架构概览保存的文件名（位于项目的 docs/ 目录）
+pub const OVERVIEW_FILE: &str = "architecture.md";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodebaseScan {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<LanguageReport>,
+    pub entry_points: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    pub manifests: Vec<String>,
+    pub file_count: usize,
+}
+
+/// 扫描结果及构建提示词所需的文件内容
+pub struct ScanContext {
+    pub scan: CodebaseScan,
+    pub readme_content: Option<String>,
+    pub manifest_contents: Vec<(String, String)>,
+    pub entry_contents: Vec<(String, String)>,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingResult {
+    pub project: crate::Project,
+    pub scan: CodebaseScan,
+    /// 架构概览文档的路径（相对于项目目录）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overview_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    /// 未能完成的步骤（例如 AI 服务不可用），导入本身仍然成功
+    pub warnings: Vec<String>,
+}
+
+// ===== 扫描 =====
+
+/// 常见的入口文件
+const ENTRY_CANDIDATES: [&str; 16] = [
+    "main.py",
+    "app.py",
+    "manage.py",
+    "__main__.py",
+    "src/main.py",
+    "src/main.rs",
+    "src/lib.rs",
+    "index.js",
+    "main.js",
+    "server.js",
+    "app.js",
+    "src/index.js",
+    "src/index.ts",
+    "src/main.ts",
+    "main.go",
+    "index.html",
+];
+
+/// 查找入口文件
+pub fn find_entry_points(root: &Path) -> Vec<String> {
+    let mut entries: Vec<String> = ENTRY_CANDIDATES
+        .iter()
+        .filter(|c| root.join(c).is_file())
+        .map(|c| c.to_string())
+        .collect();
+
+    // package.json 中声明的入口
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        if let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) {
+            if let Some(main) = package["main"].as_str() {
+                let main = main.trim_start_matches("./").to_string();
+                if root.join(&main).is_file() && !entries.contains(&main) {
+                    entries.push(main);
+                }
+            }
+        }
+    }
+
+    // Cargo 的额外二进制目标
+    if let Ok(bins) = fs::read_dir(root.join("src/bin")) {
+        for bin in bins.flatten() {
+            let path = bin.path();
+            if path.extension().map(|e| e == "rs").unwrap_or(false) {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    entries.push(format!("src/bin/{}", name));
+                }
+            }
+        }
+    }
+
+    entries
+}
+
+/// 查找 README 文件（不区分大小写）
+fn find_readme(root: &Path) -> Option<String> {
+    let mut candidates: Vec<String> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| e.file_name().to_str().map(|n| n.to_string()))
+        .filter(|n| n.to_lowercase().starts_with("readme"))
+        .collect();
+    // README.md 优先于其他语言版本
+    candidates.sort_by_key(|n| (n.to_lowercase() != "readme.md", n.len()));
+    candidates.into_iter().next()
+}
+
+fn read_limited(path: &Path, limit: usize) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    if content.len() <= limit {
+        return Some(content);
+    }
+    let mut end = limit;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(format!("{}\n...（内容过长，已截断）", &content[..end]))
+}
+
+fn collect_files(dir: &Path, base: &Path, files: &mut Vec<String>, total: &mut usize) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    let mut entries: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    entries.sort();
+
+    for path in entries {
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            collect_files(&path, base, files, total);
+            continue;
+        }
+        *total += 1;
+        if files.len() < MAX_LISTED_FILES {
+            if let Ok(relative) = path.strip_prefix(base) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+}
+
+/// 扫描代码目录
+pub fn scan_codebase(root: &Path) -> ScanContext {
+    let languages = language::detect_languages(root);
+    let entry_points = find_entry_points(root);
+    let readme = find_readme(root);
+    let readme_content = readme
+        .as_ref()
+        .and_then(|name| read_limited(&root.join(name), README_LIMIT));
+
+    let manifests = languages
+        .as_ref()
+        .map(|report| report.manifests.clone())
+        .unwrap_or_default();
+    let manifest_contents = manifests
+        .iter()
+        .filter_map(|name| read_limited(&root.join(name), MANIFEST_LIMIT).map(|c| (name.clone(), c)))
+        .collect();
+    let entry_contents = entry_points
+        .iter()
+        .take(3)
+        .filter_map(|name| read_limited(&root.join(name), ENTRY_LIMIT).map(|c| (name.clone(), c)))
+        .collect();
+
+    let mut files = Vec::new();
+    let mut file_count = 0;
+    collect_files(root, root, &mut files, &mut file_count);
+
+    ScanContext {
+        scan: CodebaseScan {
+            languages,
+            entry_points,
+            readme,
+            manifests,
+            file_count,
+        },
+        readme_content,
+        manifest_contents,
+        entry_contents,
+        files,
+    }
+}
+
+/// README 的第一段正文，用作项目描述
+pub fn readme_summary(content: &str) -> Option<String> {
+    content
+        .split("\n\n")
+        .map(|p| p.trim())
+        .find(|p| {
+            !p.is_empty()
+                && !p.starts_with('#')
+                && !p.starts_with("![")
+                && !p.starts_with("[![")
+                && !p.starts_with('<')
+                && !p.starts_with("```")
+        })
+        .map(|p| p.lines().map(|l| l.trim()).collect::<Vec<_>>().join(" "))
+        .map(|p| p.chars().take(200).collect())
+}
+
+// ===== 提示词 =====
+
+/// 扫描结果的文字描述，供各提示词共用
+fn describe_scan(ctx: &ScanContext) -> String {
+    let languages = ctx
+        .scan
+        .languages
+        .as_ref()
+        .map(|report| {
+            report
+                .breakdown
+                .iter()
+                .map(|s| format!("{} {:.1}%（{} 个文件）", s.name, s.percent, s.files))
+                .collect::<Vec<_>>()
+                .join("，")
+        })
+        .unwrap_or_else(|| "未识别".to_string());
+
+    let mut sections = vec![
+        format!("## 语言构成\n{}", languages),
+        format!(
+            "## 入口文件\n{}",
+            if ctx.scan.entry_points.is_empty() {
+                "未找到".to_string()
+            } else {
+                ctx.scan.entry_points.join("\n")
+            }
+        ),
+        format!(
+            "## 文件列表（共 {} 个文件）\n{}",
+            ctx.scan.file_count,
+            ctx.files.join("\n")
+        ),
+    ];
+
+    for (name, content) in &ctx.manifest_contents {
+        sections.push(format!("## {}\n```\n{}\n```", name, content));
+    }
+    if let (Some(name), Some(content)) = (&ctx.scan.readme, &ctx.readme_content) {
+        sections.push(format!("## {}\n```markdown\n{}\n```", name, content));
+    }
+    for (name, content) in &ctx.entry_contents {
+        sections.push(format!("## 入口文件 {}\n```\n{}\n```", name, content));
+    }
+
+    sections.join("\n\n")
+}
+
+/// 构建生成架构概览的提示词
+pub fn build_overview_prompt(project_name: &str, root: &str, ctx: &ScanContext) -> String {
+    format!(
+        "你是 Code Sensei 的编程老师，一位学生接手了一个已有的项目「{}」，需要快速了解它。
+
+## 项目路径
+{}
+
+{}
+
+## 任务
+可以用 Read 工具阅读项目中的其他文件，然后用中文写一份面向初学者的架构概览，包含：
+- 项目是做什么的
+- 目录结构和主要模块的职责
+- 程序从哪里开始运行，主要的数据和调用流程
+- 使用的框架和依赖
+- 如何运行和测试
+- 建议先阅读的文件
+
+## 要求
+- 不要修改、创建或删除任何文件
+- 使用 Markdown 格式，以一级标题开头
+- 直接输出文档内容，不要有其他说明",
+        project_name,
+        root,
+        describe_scan(ctx)
+    )
+}
+
+/// 构建根据现有代码生成需求文档的提示词
+pub fn build_requirement_prompt(project_name: &str, ctx: &ScanContext, overview: Option<&str>) -> String {
+    let overview = overview
+        .map(|o| format!("## 架构概览\n{}\n\n", o))
+        .unwrap_or_default();
+
+    format!(
+        "你是 Code Sensei 的需求文档编辑助手。项目「{}」已经有代码，请根据代码描述它**已经实现**的功能，写一份初始需求文档。
+
+{}{}
+
+## 任务
+根据上面的信息整理需求文档，只描述代码中已经存在的功能，不要编造尚未实现的功能。
+
+## 输出格式
+严格按照 Markdown 格式输出完整的需求文档，包含：
+- 项目描述
+- 功能需求
+- 技术栈
+- 其他必要章节
+
+请直接输出需求文档内容，不要有其他说明。",
+        project_name,
+        overview,
+        describe_scan(ctx)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readme_summary() {
+        let readme = "# Demo\n\n[![build](badge.svg)](ci)\n\nA small tool\nthat does things.\n\n## Usage\n";
+        assert_eq!(readme_summary(readme).as_deref(), Some("A small tool that does things."));
+    }
+
+    #[test]
+    fn test_find_entry_points() {
+        let root = std::env::temp_dir().join(format!("code-sensei-onboard-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/bin/tool.rs"), "fn main() {}\n").unwrap();
+
+        let entries = find_entry_points(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(entries, vec!["src/main.rs", "src/bin/tool.rs"]);
+    }
+}
@@ -0,0 +1,308 @@
+// 项目修复：扫描时收集损坏的项目，并支持根据目录内容重建元数据或移入隔离目录
+use crate::{language, migration, relink, Project};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// 目录中出现这些文件时，即使缺少 project.json 也认为是项目
+const PROJECT_MARKERS: [&str; 4] = ["requirement.md", "chat.json", "tasks.json", "src"];
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenKind {
+    /// 缺少 project.json
+    MissingMetadata,
+    /// project.json 无法读取
+    Unreadable,
+    /// project.json 内容无效
+    Invalid,
+    /// 由更新版本的 Code Sensei 创建，不能自动修复
+    NewerVersion,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenProject {
+    /// 项目目录名，即项目 id
+    pub id: String,
+    pub path: String,
+    pub kind: BrokenKind,
+    pub reason: String,
+    pub repairable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectScan {
+    pub projects: Vec<Project>,
+    pub needs_repair: Vec<BrokenProject>,
+}
+
+// ===== 扫描 =====
+
+fn broken(project_dir: &Path, kind: BrokenKind, reason: String) -> BrokenProject {
+    BrokenProject {
+        id: dir_name(project_dir),
+        path: project_dir.display().to_string(),
+        repairable: kind != BrokenKind::NewerVersion,
+        kind,
+        reason,
+    }
+}
+
+fn dir_name(project_dir: &Path) -> String {
+    project_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 检查单个项目目录，不是项目的目录返回 None
+pub fn check_project(project_dir: &Path) -> Option<Result<Project, BrokenProject>> {
+    let meta_file = project_dir.join("project.json");
+    if !meta_file.exists() {
+        let is_project = PROJECT_MARKERS.iter().any(|m| project_dir.join(m).exists());
+        return is_project.then(|| {
+            Err(broken(project_dir, BrokenKind::MissingMetadata, "缺少 project.json".to_string()))
+        });
+    }
+
+    let content = match fs::read_to_string(&meta_file) {
+        Ok(content) => content,
+        Err(e) => {
+            return Some(Err(broken(
+                project_dir,
+                BrokenKind::Unreadable,
+                format!("无法读取 project.json: {}", e),
+            )))
+        }
+    };
+
+    let value: Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(Err(broken(
+                project_dir,
+                BrokenKind::Invalid,
+                format!("无法解析 project.json: {}", e),
+            )))
+        }
+    };
+    let version = migration::schema_version(&value);
+    if version > migration::SCHEMA_VERSION {
+        return Some(Err(broken(
+            project_dir,
+            BrokenKind::NewerVersion,
+            format!("项目数据版本 {} 过新，请升级 Code Sensei", version),
+        )));
+    }
+
+    let result = migration::load_project_json(project_dir)
+        .and_then(|value| {
+            serde_json::from_value::<Project>(value).map_err(|e| format!("无法解析 project.json: {}", e))
+        })
+        .map_err(|reason| broken(project_dir, BrokenKind::Invalid, reason));
+    Some(result)
+}
+
+/// 标记关联目录已不存在的项目；目录存在但还没有文件快照时补充记录
+fn check_root(project_dir: &Path, project: &mut Project) {
+    let Some(ref root_path) = project.root_path else { return };
+    let root = Path::new(root_path);
+
+    if !root.is_dir() {
+        println!("⚠️ 项目关联的目录不存在: {} ({})", project.name, root_path);
+        project.root_missing = true;
+    } else if !project_dir.join(relink::SNAPSHOT_FILE).exists() {
+        if let Err(e) = relink::save_snapshot(project_dir, root) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// 扫描项目目录，单个项目出错不影响其他项目
+pub fn scan(projects_dir: &Path) -> Result<ProjectScan, String> {
+    let entries = fs::read_dir(projects_dir)
+        .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+
+    let mut projects = Vec::new();
+    let mut needs_repair = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        match check_project(&path) {
+            Some(Ok(mut project)) => {
+                check_root(&path, &mut project);
+                projects.push(project);
+            }
+            Some(Err(broken)) => {
+                println!("⚠️ 项目需要修复: {} ({})", broken.id, broken.reason);
+                needs_repair.push(broken);
+            }
+            None => {}
+        }
+    }
+
+    projects.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    needs_repair.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(ProjectScan { projects, needs_repair })
+}
+
+// ===== 修复 =====
+
+/// 从需求文档中读取项目名称和描述
+fn requirement_info(content: &str) -> (Option<String>, Option<String>) {
+    let name = content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().trim_end_matches("需求文档").trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    let description = content
+        .split("\n## ")
+        .find_map(|section| section.strip_prefix("项目描述"))
+        .map(|body| body.trim().to_string())
+        .filter(|body| !body.is_empty());
+
+    (name, description)
+}
+
+/// 根据目录内容重建项目元数据
+///
+/// 尽量保留损坏的 project.json 中仍能读出的字段，其余从 requirement.md 和代码推断。
+/// 原文件备份为 project.json.broken-<时间戳>.bak。
+pub fn rebuild_project(project_dir: &Path) -> Result<Project, String> {
+    let meta_file = project_dir.join("project.json");
+    let old_content = fs::read_to_string(&meta_file).ok();
+    let old: Value = old_content
+        .as_deref()
+        .and_then(|c| serde_json::from_str(c).ok())
+        .unwrap_or(Value::Null);
+
+    if migration::schema_version(&old) > migration::SCHEMA_VERSION {
+        return Err("项目由更新版本的 Code Sensei 创建，请升级后再打开".to_string());
+    }
+
+    let text = |key: &str| old[key].as_str().map(|s| s.to_string()).filter(|s| !s.is_empty());
+    // 关联目录的项目，需求文档在关联目录中
+    let requirement_file = match text("root_path") {
+        Some(root_path) => Path::new(&root_path).join("requirement.md"),
+        None => project_dir.join("requirement.md"),
+    };
+    let requirement = fs::read_to_string(requirement_file).unwrap_or_default();
+    let (req_name, req_description) = requirement_info(&requirement);
+
+    let modified = fs::metadata(project_dir)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp())
+        .unwrap_or_else(|| chrono::Utc::now().timestamp());
+
+    let id = dir_name(project_dir);
+    let mut project = Project {
+        schema_version: migration::SCHEMA_VERSION,
+        name: text("name").or(req_name).unwrap_or_else(|| id.clone()),
+        id,
+        description: text("description").or(req_description).unwrap_or_default(),
+        language: text("language").unwrap_or_else(|| language::DEFAULT_LANGUAGE.to_string()),
+        languages: Vec::new(),
+        created_at: old["created_at"].as_i64().unwrap_or(modified),
+        updated_at: chrono::Utc::now().timestamp(),
+        // 关联目录暂时不可用（例如移动硬盘未连接）时也保留，由重新关联处理
+        root_path: text("root_path"),
+        run_command: text("run_command"),
+        test_command: text("test_command"),
+        tags: old["tags"]
+            .as_array()
+            .map(|tags| tags.iter().filter_map(|t| t.as_str().map(|t| t.to_string())).collect())
+            .unwrap_or_default(),
+        pinned: old["pinned"].as_bool().unwrap_or(false),
+        archived: old["archived"].as_bool().unwrap_or(false),
+        forked_from: text("forked_from"),
+        imported_from: text("imported_from"),
+        root_missing: false,
+    };
+
+    if let Some(content) = old_content {
+        let backup = project_dir.join(format!("project.json.broken-{}.bak", chrono::Utc::now().timestamp()));
+        fs::write(&backup, content).map_err(|e| format!("无法备份 project.json: {}", e))?;
+    }
+
+    let root = crate::source_root(&project, project_dir);
+    if let Some(report) = language::detect_languages(&root) {
+        project.language = report.primary;
+        project.languages = report.breakdown;
+    }
+
+    crate::save_project(project_dir, &project)?;
+    check_root(project_dir, &mut project);
+    Ok(project)
+}
+
+/// 把项目目录移入隔离目录，返回新的位置
+pub fn quarantine(project_dir: &Path, quarantine_dir: &Path) -> Result<String, String> {
+    if !project_dir.is_dir() {
+        return Err("项目不存在".to_string());
+    }
+    fs::create_dir_all(quarantine_dir).map_err(|e| format!("无法创建隔离目录: {}", e))?;
+
+    let target = quarantine_dir.join(format!("{}-{}", dir_name(project_dir), chrono::Utc::now().timestamp()));
+    fs::rename(project_dir, &target).map_err(|e| format!("无法移动项目目录: {}", e))?;
+    Ok(target.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirement_info() {
+        let content = "# 计算器 需求文档\n\n## 项目描述\n一个简单的计算器\n\n## 功能需求\n";
+        let (name, description) = requirement_info(content);
+        assert_eq!(name.as_deref(), Some("计算器"));
+        assert_eq!(description.as_deref(), Some("一个简单的计算器"));
+    }
+
+    #[test]
+    fn test_scan_collects_broken_projects() {
+        let base = std::env::temp_dir().join(format!("code-sensei-recovery-{}", std::process::id()));
+        fs::create_dir_all(base.join("good")).unwrap();
+        fs::create_dir_all(base.join("bad")).unwrap();
+        fs::create_dir_all(base.join("empty")).unwrap();
+        fs::write(
+            base.join("good/project.json"),
+            r#"{"schema_version":1,"id":"good","name":"Good","description":"","language":"Python","created_at":1,"updated_at":1}"#,
+        )
+        .unwrap();
+        fs::write(base.join("bad/project.json"), "{ not json").unwrap();
+        fs::write(base.join("bad/requirement.md"), "# Bad 需求文档\n").unwrap();
+
+        let result = scan(&base).unwrap();
+        let repaired = rebuild_project(&base.join("bad")).unwrap();
+        let rescanned = scan(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(result.projects.len(), 1);
+        assert_eq!(result.needs_repair.len(), 1);
+        assert_eq!(result.needs_repair[0].kind, BrokenKind::Invalid);
+        assert_eq!(repaired.name, "Bad");
+        assert_eq!(rescanned.projects.len(), 2);
+    }
+
+    #[test]
+    fn test_rebuild_keeps_missing_root() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-recovery-root-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("project.json"), r#"{"name":"Linked","root_path":"/nonexistent/code-sensei-root","created_at":"x"}"#).unwrap();
+
+        let repaired = rebuild_project(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(repaired.root_path.as_deref(), Some("/nonexistent/code-sensei-root"));
+        assert!(repaired.root_missing);
+    }
+}
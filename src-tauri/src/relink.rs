@@ -0,0 +1,196 @@
+// 重新关联项目目录：记录关联目录的文件快照，目录移动后通过对比文件名确认新目录是同一份代码
+use crate::ignore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// 文件快照保存的文件名（位于项目目录）
+pub const SNAPSHOT_FILE: &str = "root_snapshot.json";
+
+/// 快照最多记录的文件数
+const MAX_SNAPSHOT_FILES: usize = 500;
+
+/// 快照最大扫描深度
+const MAX_SNAPSHOT_DEPTH: u32 = 6;
+
+/// 文件名重合度达到该比例才认为是同一份代码
+const SAME_CODEBASE_THRESHOLD: f64 = 0.5;
+
+/// 结果中最多列出的差异文件数
+const MAX_LISTED_DIFF: usize = 20;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootSnapshot {
+    pub root_path: String,
+    pub taken_at: i64,
+    /// 相对路径，按名称排序
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelinkCheck {
+    pub new_root: String,
+    /// 是否有旧目录的文件快照可以对比
+    pub snapshot_available: bool,
+    /// 文件名重合度（0-1），没有快照时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f64>,
+    pub matched: usize,
+    /// 旧目录中有、新目录中没有的文件（最多列出 20 个）
+    pub missing: Vec<String>,
+    /// 新目录中多出的文件（最多列出 20 个）
+    pub added: Vec<String>,
+    pub same_codebase: bool,
+}
+
+// ===== 快照 =====
+
+fn collect_files(dir: &Path, base: &Path, depth: u32, files: &mut Vec<String>) {
+    if depth > MAX_SNAPSHOT_DEPTH || files.len() >= MAX_SNAPSHOT_FILES {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    let mut entries: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    entries.sort();
+
+    for path in entries {
+        if files.len() >= MAX_SNAPSHOT_FILES {
+            return;
+        }
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            collect_files(&path, base, depth + 1, files);
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+/// 记录目录中的文件列表
+pub fn take_snapshot(root: &Path) -> RootSnapshot {
+    let mut files = Vec::new();
+    collect_files(root, root, 0, &mut files);
+    files.sort();
+
+    RootSnapshot {
+        root_path: root.display().to_string(),
+        taken_at: chrono::Utc::now().timestamp(),
+        files,
+    }
+}
+
+/// 保存关联目录的文件快照
+pub fn save_snapshot(project_dir: &Path, root: &Path) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(&take_snapshot(root))
+        .map_err(|e| format!("无法序列化文件快照: {}", e))?;
+    fs::write(project_dir.join(SNAPSHOT_FILE), content).map_err(|e| format!("无法保存文件快照: {}", e))
+}
+
+pub fn load_snapshot(project_dir: &Path) -> Option<RootSnapshot> {
+    let content = fs::read_to_string(project_dir.join(SNAPSHOT_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// ===== 对比 =====
+
+/// 对比旧快照和新目录，判断是否为同一份代码
+///
+/// 先比较完整相对路径，目录结构变化时再退回到只比较文件名。
+pub fn compare(snapshot: Option<&RootSnapshot>, new_root: &Path) -> RelinkCheck {
+    let current = take_snapshot(new_root);
+    let new_root = new_root.display().to_string();
+
+    let Some(snapshot) = snapshot.filter(|s| !s.files.is_empty()) else {
+        return RelinkCheck {
+            new_root,
+            snapshot_available: false,
+            similarity: None,
+            matched: 0,
+            missing: Vec::new(),
+            added: Vec::new(),
+            // 没有快照可以对比时，只要新目录不为空就允许关联
+            same_codebase: !current.files.is_empty(),
+        };
+    };
+
+    let old: HashSet<&str> = snapshot.files.iter().map(|f| f.as_str()).collect();
+    let new: HashSet<&str> = current.files.iter().map(|f| f.as_str()).collect();
+
+    let by_path = jaccard(&old, &new);
+    let old_names: HashSet<&str> = old.iter().map(|f| file_name(f)).collect();
+    let new_names: HashSet<&str> = new.iter().map(|f| file_name(f)).collect();
+    let by_name = jaccard(&old_names, &new_names);
+    let similarity = by_path.max(by_name);
+
+    let list = |a: &HashSet<&str>, b: &HashSet<&str>| {
+        let mut diff: Vec<String> = a.difference(b).map(|f| f.to_string()).collect();
+        diff.sort();
+        diff.truncate(MAX_LISTED_DIFF);
+        diff
+    };
+
+    RelinkCheck {
+        new_root,
+        snapshot_available: true,
+        similarity: Some((similarity * 1000.0).round() / 1000.0),
+        matched: old.intersection(&new).count(),
+        missing: list(&old, &new),
+        added: list(&new, &old),
+        same_codebase: similarity >= SAME_CODEBASE_THRESHOLD,
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn jaccard(a: &HashSet<&str>, b: &HashSet<&str>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tree(root: &Path, files: &[&str]) {
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compare_same_and_different_codebase() {
+        let base = std::env::temp_dir().join(format!("code-sensei-relink-{}", std::process::id()));
+        let old_root = base.join("old");
+        write_tree(&old_root, &["main.py", "app/models.py", "app/views.py", "README.md"]);
+        let snapshot = take_snapshot(&old_root);
+
+        let moved = base.join("moved");
+        write_tree(&moved, &["main.py", "app/models.py", "app/views.py", "README.md", "app/new.py"]);
+        let other = base.join("other");
+        write_tree(&other, &["src/main.rs", "Cargo.toml"]);
+
+        let same = compare(Some(&snapshot), &moved);
+        let different = compare(Some(&snapshot), &other);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(same.same_codebase);
+        assert_eq!(same.matched, 4);
+        assert_eq!(same.added, vec!["app/new.py"]);
+        assert!(!different.same_codebase);
+        assert_eq!(different.matched, 0);
+    }
+}
@@ -0,0 +1,367 @@
+// 项目替换：先预览每个文件的修改，再按用户选择的文件和匹配一次性写入，全部成功或全部不写
+use crate::docs::fingerprint;
+use crate::history;
+use crate::ignore;
+use crate::search::{self, Matcher, SearchOptions};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 写入时使用的临时文件后缀
+pub const TEMP_SUFFIX: &str = ".code-sensei-replace";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplaceOptions {
+    #[serde(flatten)]
+    pub search: SearchOptions,
+    /// 替换内容，正则模式下可以使用 $1、${name} 引用捕获组
+    pub replacement: String,
+}
+
+/// 一处替换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceEdit {
+    /// 在文件中的序号，应用时用来选择匹配
+    pub index: usize,
+    /// 匹配开始的行号（从 1 开始）
+    pub line: usize,
+    pub matched: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub path: String,
+    /// 预览时文件内容的指纹，应用时用来判断文件是否被修改
+    pub hash: String,
+    pub edits: Vec<ReplaceEdit>,
+    /// 替换全部匹配后的 unified diff
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacePreview {
+    pub files: Vec<FilePreview>,
+    pub match_count: usize,
+}
+
+/// 用户选择要替换的文件和匹配
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSelection {
+    pub path: String,
+    pub hash: String,
+    /// 要替换的匹配序号，为空时替换全部
+    #[serde(default)]
+    pub edits: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplaceResult {
+    /// 是否已写入；有文件在预览后被修改时不写入任何文件
+    pub applied: bool,
+    pub changed_files: Vec<String>,
+    pub replacements: usize,
+    /// 预览后在磁盘上被修改或删除的文件
+    pub conflicts: Vec<String>,
+    /// 符号链接不替换
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
+// ===== 预览 =====
+
+/// 计算文件中的全部替换，返回 (替换, 每处替换的字节范围)。与搜索一样逐行匹配，`^`、`$` 对应行首和行尾
+fn find_edits(matcher: &Matcher, options: &ReplaceOptions, content: &str) -> Vec<(ReplaceEdit, (usize, usize))> {
+    let regex = matcher.regex();
+    let mut edits = Vec::new();
+    let mut offset = 0;
+
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.trim_end_matches('\n').trim_end_matches('\r');
+        for caps in regex.captures_iter(line) {
+            let Some(m) = caps.get(0) else { continue };
+            if m.start() == m.end() {
+                continue;
+            }
+            let replacement = if options.search.regex {
+                let mut expanded = String::new();
+                caps.expand(&options.replacement, &mut expanded);
+                expanded
+            } else {
+                options.replacement.clone()
+            };
+            let edit = ReplaceEdit {
+                index: edits.len(),
+                line: i + 1,
+                matched: m.as_str().to_string(),
+                replacement,
+            };
+            edits.push((edit, (offset + m.start(), offset + m.end())));
+        }
+        offset += raw.len();
+    }
+    edits
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).map(|m| m.file_type().is_symlink()).unwrap_or(false)
+}
+
+/// 相对路径是否在项目内，并且没有被文件树的规则忽略
+fn is_searchable(root: &Path, relative: &str) -> bool {
+    let relative = Path::new(relative);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return false;
+    }
+    !relative
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| ignore::is_ignored(&root.join(p), p != relative))
+}
+
+/// 只应用选中的替换
+fn apply_edits(content: &str, edits: &[(ReplaceEdit, (usize, usize))], selected: Option<&[usize]>) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut count = 0;
+    for (edit, (start, end)) in edits {
+        if selected.map(|s| !s.contains(&edit.index)).unwrap_or(false) {
+            continue;
+        }
+        out.push_str(&content[last..*start]);
+        out.push_str(&edit.replacement);
+        last = *end;
+        count += 1;
+    }
+    out.push_str(&content[last..]);
+    (out, count)
+}
+
+pub fn preview(root: &Path, options: &ReplaceOptions) -> Result<ReplacePreview, String> {
+    let matcher = Matcher::new(&options.search)?;
+    let mut files = Vec::new();
+    search::collect_files(root, root, &mut files);
+
+    let mut result = ReplacePreview {
+        files: Vec::new(),
+        match_count: 0,
+    };
+    for (relative, path) in files {
+        if !matcher.accepts(&relative) || is_symlink(&path) {
+            continue;
+        }
+        let Some(content) = search::read_text(&path) else { continue };
+        let edits = find_edits(&matcher, options, &content);
+        if edits.is_empty() {
+            continue;
+        }
+        let (replaced, _) = apply_edits(&content, &edits, None);
+        result.match_count += edits.len();
+        result.files.push(FilePreview {
+            diff: history::diff(&content, &replaced, &relative, &relative).unified,
+            hash: fingerprint(content.as_bytes()),
+            edits: edits.into_iter().map(|(edit, _)| edit).collect(),
+            path: relative,
+        });
+    }
+    Ok(result)
+}
+
+// ===== 应用 =====
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(TEMP_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// 写入临时文件，并使用原文件的权限（例如可执行脚本）
+fn write_temp(path: &Path, content: &str) -> std::io::Result<()> {
+    let permissions = fs::metadata(path)?.permissions();
+    let temp = temp_path(path);
+    fs::write(&temp, content)?;
+    fs::set_permissions(&temp, permissions)
+}
+
+/// 写入多个文件：先全部写入临时文件，再逐个替换原文件，中途失败时恢复已替换的文件
+fn write_all(files: &[(PathBuf, String, String)]) -> Result<(), String> {
+    for (i, (path, _, content)) in files.iter().enumerate() {
+        if let Err(e) = write_temp(path, content) {
+            for (path, _, _) in &files[..=i] {
+                let _ = fs::remove_file(temp_path(path));
+            }
+            return Err(format!("无法写入 {}: {}", path.display(), e));
+        }
+    }
+
+    for (i, (path, _, _)) in files.iter().enumerate() {
+        if let Err(e) = fs::rename(temp_path(path), path) {
+            for (path, original, _) in &files[..i] {
+                let _ = fs::write(path, original);
+            }
+            for (path, _, _) in &files[i..] {
+                let _ = fs::remove_file(temp_path(path));
+            }
+            return Err(format!("无法替换 {}: {}", path.display(), e));
+        }
+    }
+    Ok(())
+}
+
+/// 按选择应用替换；任何文件在预览后被修改时不写入任何文件，并在结果中列出这些文件
+pub fn apply(root: &Path, options: &ReplaceOptions, selections: &[FileSelection]) -> Result<ReplaceResult, String> {
+    let matcher = Matcher::new(&options.search)?;
+    let mut result = ReplaceResult::default();
+    let mut writes = Vec::new();
+
+    for selection in selections {
+        let relative = selection.path.replace('\\', "/");
+        if !is_searchable(root, &relative) || !matcher.accepts(&relative) {
+            return Err(format!("无效的文件路径: {}", selection.path));
+        }
+        let path = root.join(&relative);
+        // 替换会把符号链接变成普通文件，跳过
+        if is_symlink(&path) {
+            result.skipped.push(relative);
+            continue;
+        }
+        let content = match search::read_text(&path) {
+            Some(content) if fingerprint(content.as_bytes()) == selection.hash => content,
+            _ => {
+                result.conflicts.push(relative);
+                continue;
+            }
+        };
+
+        let edits = find_edits(&matcher, options, &content);
+        let (replaced, count) = apply_edits(&content, &edits, selection.edits.as_deref());
+        if count == 0 || replaced == content {
+            continue;
+        }
+        result.replacements += count;
+        result.changed_files.push(relative);
+        writes.push((path, content, replaced));
+    }
+
+    if !result.conflicts.is_empty() {
+        result.changed_files.clear();
+        result.replacements = 0;
+        return Ok(result);
+    }
+
+    write_all(&writes)?;
+    result.applied = true;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(query: &str, replacement: &str, regex: bool) -> ReplaceOptions {
+        ReplaceOptions {
+            search: SearchOptions {
+                query: query.to_string(),
+                regex,
+                case_sensitive: true,
+                ..Default::default()
+            },
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_preview_and_apply_selected() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-replace-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.py"), "def add(a, b):\n    return add_impl(a, b)\n").unwrap();
+        fs::write(dir.join("b.py"), "x = add(1, 2)\n").unwrap();
+
+        let opts = options(r"add\((\w+), (\w+)\)", "plus($2, $1)", true);
+        let preview = preview(&dir, &opts).unwrap();
+        let selections: Vec<FileSelection> = preview
+            .files
+            .iter()
+            .map(|f| FileSelection {
+                path: f.path.clone(),
+                hash: f.hash.clone(),
+                edits: (f.path == "a.py").then(|| vec![0]),
+            })
+            .collect();
+        let result = apply(&dir, &opts, &selections).unwrap();
+        let a = fs::read_to_string(dir.join("a.py")).unwrap();
+        let b = fs::read_to_string(dir.join("b.py")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(preview.match_count, 2);
+        assert_eq!(preview.files[0].edits[0].replacement, "plus(b, a)");
+        assert!(preview.files[1].diff.contains("+x = plus(2, 1)"));
+        assert!(result.applied);
+        assert_eq!(result.replacements, 2);
+        assert_eq!(a, "def plus(b, a):\n    return add_impl(a, b)\n");
+        assert_eq!(b, "x = plus(2, 1)\n");
+    }
+
+    #[test]
+    fn test_apply_rejects_files_changed_since_preview() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-replace-conflict-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "hello\n").unwrap();
+        fs::write(dir.join("b.txt"), "hello\n").unwrap();
+
+        let opts = options("hello", "hi", false);
+        let selections: Vec<FileSelection> = preview(&dir, &opts)
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|f| FileSelection { path: f.path, hash: f.hash, edits: None })
+            .collect();
+        fs::write(dir.join("b.txt"), "hello world\n").unwrap();
+        let result = apply(&dir, &opts, &selections).unwrap();
+        let a = fs::read_to_string(dir.join("a.txt")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!result.applied);
+        assert_eq!(result.conflicts, vec!["b.txt"]);
+        assert_eq!(a, "hello\n");
+    }
+
+    #[test]
+    fn test_apply_matches_per_line_and_checks_paths() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-replace-lines-{}", std::process::id()));
+        fs::create_dir_all(dir.join("node_modules")).unwrap();
+        fs::write(dir.join("a.sh"), "echo a\necho b\n").unwrap();
+        fs::write(dir.join("node_modules/x.js"), "echo\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir.join("a.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let opts = options(r"^echo (\w)$", "print $1", true);
+        let preview = preview(&dir, &opts).unwrap();
+        let selection = |path: &str| FileSelection { path: path.to_string(), hash: preview.files[0].hash.clone(), edits: None };
+        let result = apply(&dir, &opts, &[selection("a.sh")]).unwrap();
+        let escaped = apply(&dir, &opts, &[selection("node_modules/x.js")]);
+        let absolute = apply(&dir, &opts, &[selection(&dir.join("a.sh").display().to_string())]);
+        let content = fs::read_to_string(dir.join("a.sh")).unwrap();
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            fs::metadata(dir.join("a.sh")).unwrap().permissions().mode() & 0o777
+        };
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(preview.match_count, 2);
+        assert_eq!(preview.files[0].edits[1].line, 2);
+        assert!(result.applied);
+        assert_eq!(content, "print a\nprint b\n");
+        assert!(escaped.is_err());
+        assert!(absolute.is_err());
+        #[cfg(unix)]
+        assert_eq!(mode, 0o755);
+    }
+}
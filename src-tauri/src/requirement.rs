@@ -0,0 +1,601 @@
+// 需求文档结构化模型：把 requirement.md 解析为描述、功能需求、非功能需求、技术栈、验收标准和待确认问题，
+// 未修改的部分按原文输出，保证解析再生成后内容不变
+use serde::{Deserialize, Serialize};
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    Description,
+    Functional,
+    NonFunctional,
+    TechStack,
+    Acceptance,
+    OpenQuestions,
+    Other,
+}
+
+impl SectionKind {
+    /// 条目 id 的前缀，没有条目的章节返回 None
+    pub fn id_prefix(self) -> Option<&'static str> {
+        match self {
+            SectionKind::Functional => Some("FR"),
+            SectionKind::NonFunctional => Some("NFR"),
+            SectionKind::TechStack => Some("TS"),
+            SectionKind::Acceptance => Some("AC"),
+            SectionKind::OpenQuestions => Some("Q"),
+            SectionKind::Description | SectionKind::Other => None,
+        }
+    }
+
+    /// 新建章节时使用的标题
+    pub fn default_heading(self) -> &'static str {
+        match self {
+            SectionKind::Description => "项目描述",
+            SectionKind::Functional => "功能需求",
+            SectionKind::NonFunctional => "非功能需求",
+            SectionKind::TechStack => "技术栈",
+            SectionKind::Acceptance => "验收标准",
+            SectionKind::OpenQuestions => "待确认问题",
+            SectionKind::Other => "其他",
+        }
+    }
+
+    /// 根据章节标题识别类型
+    pub fn from_heading(heading: &str) -> SectionKind {
+        let heading = heading.trim().to_lowercase();
+        let has = |keys: &[&str]| keys.iter().any(|k| heading.contains(k));
+
+        // 「非功能需求」包含「功能需求」，需要先判断
+        if has(&["非功能", "non-functional", "nonfunctional"]) {
+            SectionKind::NonFunctional
+        } else if has(&["验收", "acceptance"]) {
+            SectionKind::Acceptance
+        } else if has(&["功能需求", "functional"]) {
+            SectionKind::Functional
+        } else if has(&["技术栈", "技术选型", "tech stack", "technology"]) {
+            SectionKind::TechStack
+        } else if has(&["待确认", "待定", "开放问题", "open question"]) {
+            SectionKind::OpenQuestions
+        } else if has(&["项目描述", "项目简介", "概述", "description", "overview"]) {
+            SectionKind::Description
+        } else {
+            SectionKind::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementItem {
+    pub id: String,
+    /// 条目内容，多行条目保留后续行的原始缩进
+    pub text: String,
+    /// id 是否写在文档中，否则为解析时按顺序分配
+    pub explicit_id: bool,
+    /// 原文，条目被修改后清空并重新生成
+    #[serde(skip)]
+    raw: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Block {
+    /// 列表之外的原文，例如说明段落和空行
+    Text(String),
+    Item(RequirementItem),
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    /// 「## 」所在行的原文
+    heading_line: String,
+    heading: String,
+    kind: SectionKind,
+    blocks: Vec<Block>,
+    /// 列表使用数字编号
+    ordered: bool,
+}
+
+/// 解析后的需求文档
+#[derive(Debug, Clone)]
+pub struct RequirementDoc {
+    /// 第一个「## 」之前的原文，包括一级标题
+    preamble: String,
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionSummary {
+    pub heading: String,
+    pub kind: SectionKind,
+}
+
+/// 返回给前端的结构化视图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementModel {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub description: String,
+    pub functional: Vec<RequirementItem>,
+    pub non_functional: Vec<RequirementItem>,
+    pub tech_stack: Vec<RequirementItem>,
+    pub acceptance: Vec<RequirementItem>,
+    pub open_questions: Vec<RequirementItem>,
+    pub sections: Vec<SectionSummary>,
+}
+
+// ===== 解析 =====
+
+/// 列表项的标记，返回 (是否为数字编号, 标记之后的内容)
+fn list_marker(line: &str) -> Option<(bool, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some((false, rest));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((true, rest));
+        }
+    }
+    None
+}
+
+/// 从条目开头读取 id，支持「**FR-1**」「[FR-1]」「FR-1:」等写法
+///
+/// 不带标记的写法只接受本章节的前缀，避免把「HTTP-2 支持」「UTF-8 编码」这类正文当成 id
+fn split_id<'a>(text: &'a str, prefix: &str) -> Option<(String, &'a str)> {
+    let (open, close) = if text.starts_with("**") {
+        ("**", "**")
+    } else if text.starts_with('[') {
+        ("[", "]")
+    } else {
+        ("", "")
+    };
+    let rest = &text[open.len()..];
+
+    let letters = rest.chars().take_while(|c| c.is_ascii_uppercase()).count();
+    if letters == 0 || letters > 4 || !rest[letters..].starts_with('-') {
+        return None;
+    }
+    if open.is_empty() && &rest[..letters] != prefix {
+        return None;
+    }
+    let digits = rest[letters + 1..].chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let id_len = letters + 1 + digits;
+    let id = rest[..id_len].to_string();
+
+    // 冒号可能在加粗标记之内，例如「**FR-1:**」
+    let rest = rest[id_len..].trim_start_matches([':', '：']);
+    let rest = rest.strip_prefix(close)?.trim_start_matches([':', '：']);
+    Some((id, rest.trim_start()))
+}
+
+fn parse_section(heading_line: &str, body: &[&str]) -> Section {
+    let heading = heading_line.trim_end().trim_start_matches('#').trim().to_string();
+    let kind = SectionKind::from_heading(&heading);
+    let has_items = kind.id_prefix().is_some();
+    let prefix = kind.id_prefix().unwrap_or_default();
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut ordered = false;
+    let mut current: Option<(String, String, Option<String>)> = None; // (原文, 内容, id)
+
+    let flush = |current: &mut Option<(String, String, Option<String>)>, blocks: &mut Vec<Block>| {
+        if let Some((raw, text, id)) = current.take() {
+            blocks.push(Block::Item(RequirementItem {
+                explicit_id: id.is_some(),
+                id: id.unwrap_or_default(),
+                text: text.trim_end().to_string(),
+                raw: Some(raw),
+            }));
+        }
+    };
+
+    for line in body {
+        let content = line.trim_end_matches(['\n', '\r']);
+
+        if has_items {
+            if let Some((is_ordered, rest)) = list_marker(content) {
+                flush(&mut current, &mut blocks);
+                let first_item = !blocks.iter().any(|b| matches!(b, Block::Item(_)));
+                if first_item {
+                    ordered = is_ordered;
+                }
+                let (id, text) = match split_id(rest, prefix) {
+                    Some((id, text)) => (Some(id), text.to_string()),
+                    None => (None, rest.to_string()),
+                };
+                current = Some((line.to_string(), text, id));
+                continue;
+            }
+            // 缩进的非空行属于上一个条目
+            if let Some((raw, text, _)) = current.as_mut() {
+                if !content.trim().is_empty() && content.starts_with([' ', '\t']) {
+                    raw.push_str(line);
+                    text.push('\n');
+                    text.push_str(content);
+                    continue;
+                }
+            }
+        }
+
+        flush(&mut current, &mut blocks);
+        match blocks.last_mut() {
+            Some(Block::Text(text)) => text.push_str(line),
+            _ => blocks.push(Block::Text(line.to_string())),
+        }
+    }
+    flush(&mut current, &mut blocks);
+
+    let mut section = Section {
+        heading_line: heading_line.to_string(),
+        heading,
+        kind,
+        blocks,
+        ordered,
+    };
+    section.assign_ids();
+    section
+}
+
+impl Section {
+    fn items(&self) -> impl Iterator<Item = &RequirementItem> {
+        self.blocks.iter().filter_map(|b| match b {
+            Block::Item(item) => Some(item),
+            Block::Text(_) => None,
+        })
+    }
+
+    fn items_mut(&mut self) -> impl Iterator<Item = &mut RequirementItem> {
+        self.blocks.iter_mut().filter_map(|b| match b {
+            Block::Item(item) => Some(item),
+            Block::Text(_) => None,
+        })
+    }
+
+    /// 为没有写 id 的条目按顺序分配未使用的编号
+    fn assign_ids(&mut self) {
+        let Some(prefix) = self.kind.id_prefix() else { return };
+        let mut used: Vec<String> = self.items().filter(|i| i.explicit_id).map(|i| i.id.clone()).collect();
+        let mut next = 1;
+        for item in self.items_mut() {
+            if item.explicit_id {
+                continue;
+            }
+            while used.contains(&format!("{}-{}", prefix, next)) {
+                next += 1;
+            }
+            item.id = format!("{}-{}", prefix, next);
+            used.push(item.id.clone());
+        }
+    }
+
+    /// 章节被修改后重新生成所有条目，使编号和 id 写入文档
+    fn normalize(&mut self) {
+        for item in self.items_mut() {
+            item.raw = None;
+            item.explicit_id = true;
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        out.push_str(&self.heading_line);
+        let mut number = 0;
+        for block in &self.blocks {
+            match block {
+                Block::Text(text) => out.push_str(text),
+                Block::Item(item) => {
+                    number += 1;
+                    if let Some(ref raw) = item.raw {
+                        out.push_str(raw);
+                        continue;
+                    }
+                    ensure_newline(out);
+                    if self.ordered {
+                        out.push_str(&format!("{}. ", number));
+                    } else {
+                        out.push_str("- ");
+                    }
+                    out.push_str(&format!("**{}** {}\n", item.id, item.text.trim_end()));
+                }
+            }
+        }
+    }
+}
+
+fn ensure_newline(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+impl RequirementDoc {
+    pub fn parse(content: &str) -> RequirementDoc {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut preamble = String::new();
+        let mut sections = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() && !lines[i].starts_with("## ") {
+            preamble.push_str(lines[i]);
+            i += 1;
+        }
+        while i < lines.len() {
+            let heading_line = lines[i];
+            let start = i + 1;
+            let mut end = start;
+            while end < lines.len() && !lines[end].starts_with("## ") {
+                end += 1;
+            }
+            sections.push(parse_section(heading_line, &lines[start..end]));
+            i = end;
+        }
+
+        RequirementDoc { preamble, sections }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = self.preamble.clone();
+        for section in &self.sections {
+            ensure_newline(&mut out);
+            section.render(&mut out);
+        }
+        out
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.preamble
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(|title| title.trim().to_string())
+    }
+
+    fn section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|s| s.kind == kind)
+    }
+
+    fn items(&self, kind: SectionKind) -> Vec<RequirementItem> {
+        self.section(kind).map(|s| s.items().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn description(&self) -> String {
+        self.section(SectionKind::Description)
+            .map(|s| {
+                s.blocks
+                    .iter()
+                    .map(|b| match b {
+                        Block::Text(text) => text.clone(),
+                        Block::Item(item) => item.text.clone(),
+                    })
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn model(&self) -> RequirementModel {
+        RequirementModel {
+            title: self.title(),
+            description: self.description(),
+            functional: self.items(SectionKind::Functional),
+            non_functional: self.items(SectionKind::NonFunctional),
+            tech_stack: self.items(SectionKind::TechStack),
+            acceptance: self.items(SectionKind::Acceptance),
+            open_questions: self.items(SectionKind::OpenQuestions),
+            sections: self
+                .sections
+                .iter()
+                .map(|s| SectionSummary {
+                    heading: s.heading.clone(),
+                    kind: s.kind,
+                })
+                .collect(),
+        }
+    }
+
+    /// 查找条目，返回 (章节下标, 块下标)
+    fn find_item(&self, id: &str) -> Option<(usize, usize)> {
+        self.sections.iter().enumerate().find_map(|(si, section)| {
+            section
+                .blocks
+                .iter()
+                .position(|b| matches!(b, Block::Item(item) if item.id == id))
+                .map(|bi| (si, bi))
+        })
+    }
+
+    /// 新建章节前保证文档以空行结尾
+    fn ensure_blank_line(&mut self) {
+        let rendered = self.render();
+        if rendered.is_empty() || rendered.ends_with("\n\n") {
+            return;
+        }
+        let padding = if rendered.ends_with('\n') { "\n" } else { "\n\n" };
+        match self.sections.last_mut() {
+            Some(section) => section.blocks.push(Block::Text(padding.to_string())),
+            None => self.preamble.push_str(padding),
+        }
+    }
+
+    // ===== 编辑 =====
+
+    /// 添加条目，`after` 为空时添加到章节末尾；章节不存在时新建。返回新条目的 id
+    pub fn add_item(&mut self, kind: SectionKind, text: &str, after: Option<&str>) -> Result<String, String> {
+        let prefix = kind
+            .id_prefix()
+            .ok_or_else(|| format!("「{}」章节没有条目", kind.default_heading()))?;
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("需求内容不能为空".to_string());
+        }
+
+        let si = match self.sections.iter().position(|s| s.kind == kind) {
+            Some(si) => si,
+            None => {
+                self.ensure_blank_line();
+                let heading = kind.default_heading();
+                self.sections.push(Section {
+                    heading_line: format!("## {}\n", heading),
+                    heading: heading.to_string(),
+                    kind,
+                    blocks: vec![Block::Text("\n".to_string())],
+                    ordered: false,
+                });
+                self.sections.len() - 1
+            }
+        };
+
+        let section = &mut self.sections[si];
+        let next = section
+            .items()
+            .filter_map(|i| i.id.strip_prefix(prefix)?.strip_prefix('-')?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let id = format!("{}-{}", prefix, next);
+
+        let position = match after {
+            Some(after) => {
+                let bi = section
+                    .blocks
+                    .iter()
+                    .position(|b| matches!(b, Block::Item(item) if item.id == after))
+                    .ok_or_else(|| format!("需求条目不存在: {}", after))?;
+                bi + 1
+            }
+            None => match section.blocks.iter().rposition(|b| matches!(b, Block::Item(_))) {
+                Some(last) => last + 1,
+                // 没有条目时放在章节开头的说明文字之后、末尾空行之前
+                None => match section.blocks.last() {
+                    Some(Block::Text(text)) if text.trim().is_empty() => section.blocks.len() - 1,
+                    _ => section.blocks.len(),
+                },
+            },
+        };
+
+        section.blocks.insert(
+            position,
+            Block::Item(RequirementItem {
+                id: id.clone(),
+                text: text.to_string(),
+                explicit_id: true,
+                raw: None,
+            }),
+        );
+        section.normalize();
+        Ok(id)
+    }
+
+    /// 修改条目内容
+    pub fn update_item(&mut self, id: &str, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("需求内容不能为空".to_string());
+        }
+        let (si, bi) = self.find_item(id).ok_or_else(|| format!("需求条目不存在: {}", id))?;
+        let section = &mut self.sections[si];
+        if let Block::Item(item) = &mut section.blocks[bi] {
+            item.text = text.to_string();
+        }
+        section.normalize();
+        Ok(())
+    }
+
+    /// 删除条目
+    pub fn remove_item(&mut self, id: &str) -> Result<(), String> {
+        let (si, bi) = self.find_item(id).ok_or_else(|| format!("需求条目不存在: {}", id))?;
+        let section = &mut self.sections[si];
+        section.blocks.remove(bi);
+        section.normalize();
+        Ok(())
+    }
+
+    /// 把条目移动到章节内的第 `index` 个位置（从 0 开始）
+    pub fn move_item(&mut self, id: &str, index: usize) -> Result<(), String> {
+        let (si, bi) = self.find_item(id).ok_or_else(|| format!("需求条目不存在: {}", id))?;
+        let section = &mut self.sections[si];
+
+        let item = section.blocks.remove(bi);
+        let item_positions: Vec<usize> = section
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| matches!(b, Block::Item(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let position = match item_positions.get(index) {
+            Some(&position) => position,
+            None => item_positions.last().map(|&last| last + 1).unwrap_or(bi),
+        };
+        section.blocks.insert(position, item);
+        section.normalize();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# 计算器 需求文档\n\n## 项目描述\n一个简单的计算器\n\n## 功能需求\n支持以下功能：\n1. 加减乘除\n2. **FR-7** 清空输入\n   - 按 C 键\n\n## 非功能需求\n- 响应时间小于 100ms\n\n## 技术栈\n- Python\n\n## 验收标准\n- 能计算 1+1\n";
+
+    #[test]
+    fn test_roundtrip_is_lossless() {
+        let doc = RequirementDoc::parse(SAMPLE);
+        assert_eq!(doc.render(), SAMPLE);
+
+        let no_newline = "# 标题\n\n## 功能需求\n- 登录";
+        assert_eq!(RequirementDoc::parse(no_newline).render(), no_newline);
+    }
+
+    #[test]
+    fn test_parse_model() {
+        let model = RequirementDoc::parse(SAMPLE).model();
+        assert_eq!(model.title.as_deref(), Some("计算器 需求文档"));
+        assert_eq!(model.description, "一个简单的计算器");
+        assert_eq!(model.functional.len(), 2);
+        assert_eq!(model.functional[0].id, "FR-1");
+        assert!(!model.functional[0].explicit_id);
+        assert_eq!(model.functional[1].id, "FR-7");
+        assert_eq!(model.functional[1].text, "清空输入\n   - 按 C 键");
+        assert_eq!(model.non_functional[0].id, "NFR-1");
+        assert_eq!(model.sections.last().unwrap().kind, SectionKind::Acceptance);
+        assert_eq!(model.acceptance[0].id, "AC-1");
+    }
+
+    #[test]
+    fn test_bare_id_needs_section_prefix() {
+        let doc = RequirementDoc::parse("## 功能需求\n- FR-3: 登录\n- HTTP-2 支持\n- [API-1] 接口\n\n## 非功能需求\n- UTF-8 编码\n");
+        let model = doc.model();
+        assert_eq!(model.functional[0].id, "FR-3");
+        assert_eq!(model.functional[1].text, "HTTP-2 支持");
+        assert!(!model.functional[1].explicit_id);
+        assert_eq!(model.functional[2].id, "API-1");
+        assert_eq!(model.non_functional[0].text, "UTF-8 编码");
+    }
+
+    #[test]
+    fn test_edit_items() {
+        let mut doc = RequirementDoc::parse(SAMPLE);
+        let id = doc.add_item(SectionKind::Functional, "显示历史记录", Some("FR-1")).unwrap();
+        assert_eq!(id, "FR-8");
+        doc.move_item("FR-7", 0).unwrap();
+        doc.update_item("FR-1", "四则运算").unwrap();
+        doc.add_item(SectionKind::OpenQuestions, "是否支持科学计算？", None).unwrap();
+
+        let rendered = doc.render();
+        assert!(rendered.contains(
+            "支持以下功能：\n1. **FR-7** 清空输入\n   - 按 C 键\n2. **FR-1** 四则运算\n3. **FR-8** 显示历史记录\n\n## 非功能需求"
+        ));
+        assert!(rendered.ends_with("- 能计算 1+1\n\n## 待确认问题\n- **Q-1** 是否支持科学计算？\n\n"));
+        // 未修改的章节保持原文
+        assert!(rendered.contains("## 非功能需求\n- 响应时间小于 100ms\n"));
+    }
+}
@@ -0,0 +1,557 @@
+// 本地代码运行器：在子进程中运行项目入口或指定文件，并以事件流式输出
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+
+/// 默认的运行超时时间（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// 每个输出流保留的最大字节数（用于错误解释）
+const CAPTURE_LIMIT: usize = 64 * 1024;
+
+/// 保留的已结束运行记录数
+const FINISHED_LIMIT: usize = 20;
+
+// ===== 数据结构 =====
+
+/// 解析出的运行命令
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl RunCommand {
+    fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// 解析命令行字符串（按空白分割），并替换为当前平台的可执行文件名
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let program = match parts.next()? {
+            "python" | "python3" => python_program(),
+            "npm" => npm_program(),
+            "npx" => npx_program(),
+            other => other,
+        };
+        Some(Self {
+            program: program.to_string(),
+            args: parts.map(|a| a.to_string()).collect(),
+        })
+    }
+
+    /// 用于展示给用户的命令行
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// 一次运行的输出片段（`run-output` 事件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutput {
+    pub run_id: String,
+    pub project_id: String,
+    /// "stdout" 或 "stderr"
+    pub stream: String,
+    pub data: String,
+}
+
+/// 一次运行的结束状态（`run-exited` 事件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunExit {
+    pub run_id: String,
+    pub project_id: String,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub killed: bool,
+    pub duration_ms: u64,
+}
+
+/// 已结束运行的完整记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub exit: RunExit,
+    pub stdout: String,
+    pub stderr: String,
+    /// 输出没有在限定时间内读取完成，捕获的输出可能不完整
+    #[serde(default)]
+    pub output_incomplete: bool,
+}
+
+/// 发送给子进程标准输入的消息，`None` 表示关闭标准输入（EOF）
+type StdinMessage = Option<String>;
+
+struct RunHandle {
+    stdin_tx: mpsc::UnboundedSender<StdinMessage>,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+/// 正在运行的进程表
+#[derive(Clone, Default)]
+pub struct RunnerRegistry {
+    runs: Arc<Mutex<HashMap<String, RunHandle>>>,
+    finished: Arc<Mutex<Vec<RunRecord>>>,
+}
+
+impl RunnerRegistry {
+    /// 向运行中的进程写入标准输入
+    pub fn write_stdin(&self, run_id: &str, input: Option<String>) -> Result<(), String> {
+        let runs = self.runs.lock().unwrap();
+        let handle = runs
+            .get(run_id)
+            .ok_or_else(|| format!("运行不存在或已结束: {}", run_id))?;
+        handle
+            .stdin_tx
+            .send(input)
+            .map_err(|_| "进程的标准输入已关闭".to_string())
+    }
+
+    /// 终止运行中的进程
+    pub fn kill(&self, run_id: &str) -> Result<(), String> {
+        let mut runs = self.runs.lock().unwrap();
+        let handle = runs
+            .get_mut(run_id)
+            .ok_or_else(|| format!("运行不存在或已结束: {}", run_id))?;
+        if let Some(kill_tx) = handle.kill_tx.take() {
+            let _ = kill_tx.send(());
+        }
+        Ok(())
+    }
+
+    /// 当前正在运行的 run id 列表
+    pub fn running(&self) -> Vec<String> {
+        self.runs.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// 获取已结束运行的记录
+    pub fn record(&self, run_id: &str) -> Option<RunRecord> {
+        self.finished
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.exit.run_id == run_id)
+            .cloned()
+    }
+
+    /// 获取项目最近一次结束的运行记录
+    pub fn latest_record(&self, project_id: &str) -> Option<RunRecord> {
+        self.finished
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|r| r.exit.project_id == project_id)
+            .cloned()
+    }
+
+    fn finish(&self, record: RunRecord) {
+        self.runs.lock().unwrap().remove(&record.exit.run_id);
+
+        let mut finished = self.finished.lock().unwrap();
+        finished.push(record);
+        if finished.len() > FINISHED_LIMIT {
+            let overflow = finished.len() - FINISHED_LIMIT;
+            finished.drain(..overflow);
+        }
+    }
+}
+
+// ===== 命令解析 =====
+
+/// 根据文件扩展名确定运行命令
+fn command_for_file(root: &Path, file: &str) -> Result<RunCommand, String> {
+    let ext = Path::new(file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let command = match ext.as_str() {
+        "py" => RunCommand::new(python_program(), &["-u", file]),
+        "js" | "mjs" | "cjs" => RunCommand::new("node", &[file]),
+        "ts" => RunCommand::new(npx_program(), &["--yes", "tsx", file]),
+        "go" => RunCommand::new("go", &["run", file]),
+        "java" => RunCommand::new("java", &[file]),
+        "rb" => RunCommand::new("ruby", &[file]),
+        "sh" => RunCommand::new("sh", &[file]),
+        "rs" => {
+            if !root.join("Cargo.toml").exists() {
+                return Err("运行 Rust 文件需要项目根目录下存在 Cargo.toml".to_string());
+            }
+            // src/bin/<name>.rs 作为独立的二进制目标运行
+            let path = Path::new(file);
+            let in_bin_dir = path
+                .parent()
+                .map(|p| p.ends_with("src/bin"))
+                .unwrap_or(false);
+            match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) if in_bin_dir => RunCommand::new("cargo", &["run", "--quiet", "--bin", name]),
+                _ => RunCommand::new("cargo", &["run", "--quiet"]),
+            }
+        }
+        _ => return Err(format!("不支持运行该类型的文件: {}", file)),
+    };
+
+    Ok(command)
+}
+
+/// 在项目根目录中查找入口并确定运行命令
+fn command_for_entry(root: &Path, language: &str) -> Result<RunCommand, String> {
+    if root.join("Cargo.toml").exists() {
+        return Ok(RunCommand::new("cargo", &["run", "--quiet"]));
+    }
+
+    if root.join("go.mod").exists() {
+        return Ok(RunCommand::new("go", &["run", "."]));
+    }
+
+    if let Ok(content) = std::fs::read_to_string(root.join("package.json")) {
+        if let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) {
+            if package["scripts"]["start"].is_string() {
+                return Ok(RunCommand::new(npm_program(), &["start", "--silent"]));
+            }
+            if let Some(main) = package["main"].as_str() {
+                if root.join(main).is_file() {
+                    return command_for_file(root, main);
+                }
+            }
+        }
+    }
+
+    let python_entries = ["main.py", "app.py", "run.py", "__main__.py", "src/main.py"];
+    let node_entries = ["index.js", "main.js", "app.js", "server.js", "src/index.js"];
+    let other_entries = ["main.go", "Main.java", "main.rb", "main.sh"];
+
+    // 按项目主语言决定候选入口的优先顺序
+    let mut candidates: Vec<&str> = Vec::new();
+    match language.to_lowercase().as_str() {
+        "javascript" | "typescript" => {
+            candidates.extend(node_entries);
+            candidates.extend(python_entries);
+        }
+        _ => {
+            candidates.extend(python_entries);
+            candidates.extend(node_entries);
+        }
+    }
+    candidates.extend(other_entries);
+
+    for candidate in candidates {
+        if root.join(candidate).is_file() {
+            return command_for_file(root, candidate);
+        }
+    }
+
+    Err("未找到可运行的入口文件，请指定要运行的文件".to_string())
+}
+
+/// 解析运行命令：指定了文件则运行该文件，否则使用项目配置的运行命令或查找项目入口
+pub fn resolve_command(
+    root: &Path,
+    language: &str,
+    run_command: Option<&str>,
+    file: Option<&str>,
+) -> Result<RunCommand, String> {
+    match file {
+        Some(file) if !file.trim().is_empty() => {
+            let file = file.replace('\\', "/");
+            // 只允许项目内的相对路径
+            if !Path::new(&file).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+                return Err(format!("无效的文件路径: {}", file));
+            }
+            if !root.join(&file).is_file() {
+                return Err(format!("文件不存在: {}", file));
+            }
+            command_for_file(root, &file)
+        }
+        _ => match run_command.and_then(RunCommand::parse) {
+            Some(command) => Ok(command),
+            None => command_for_entry(root, language),
+        },
+    }
+}
+
+fn python_program() -> &'static str {
+    if cfg!(windows) { "python" } else { "python3" }
+}
+
+fn npm_program() -> &'static str {
+    if cfg!(windows) { "npm.cmd" } else { "npm" }
+}
+
+fn npx_program() -> &'static str {
+    if cfg!(windows) { "npx.cmd" } else { "npx" }
+}
+
+// ===== 进程执行 =====
+
+/// 取出缓冲区中完整的 UTF-8 内容，不完整的多字节字符留到下一次读取
+fn take_utf8(buffer: &mut Vec<u8>) -> String {
+    let valid_len = match std::str::from_utf8(buffer) {
+        Ok(_) => buffer.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => buffer.len(),
+    };
+    let text = String::from_utf8_lossy(&buffer[..valid_len]).into_owned();
+    buffer.drain(..valid_len);
+    text
+}
+
+/// 追加输出并只保留末尾 `CAPTURE_LIMIT` 字节（错误信息通常在末尾）
+fn append_capped(captured: &mut String, data: &str) {
+    captured.push_str(data);
+    if captured.len() > CAPTURE_LIMIT {
+        let mut cut = captured.len() - CAPTURE_LIMIT;
+        while !captured.is_char_boundary(cut) {
+            cut += 1;
+        }
+        captured.drain(..cut);
+    }
+}
+
+/// 读取子进程输出并以 `run-output` 事件转发，返回捕获的输出
+async fn pump_output<R: AsyncRead + Unpin>(
+    mut reader: R,
+    app: AppHandle,
+    run_id: String,
+    project_id: String,
+    stream: &'static str,
+) -> String {
+    let mut chunk = [0u8; 4096];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut captured = String::new();
+
+    loop {
+        let n = match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&chunk[..n]);
+        let data = take_utf8(&mut pending);
+        if data.is_empty() {
+            continue;
+        }
+        append_capped(&mut captured, &data);
+        let _ = app.emit("run-output", RunOutput {
+            run_id: run_id.clone(),
+            project_id: project_id.clone(),
+            stream: stream.to_string(),
+            data,
+        });
+    }
+
+    if !pending.is_empty() {
+        let data = String::from_utf8_lossy(&pending).into_owned();
+        append_capped(&mut captured, &data);
+        let _ = app.emit("run-output", RunOutput {
+            run_id,
+            project_id,
+            stream: stream.to_string(),
+            data,
+        });
+    }
+
+    captured
+}
+
+/// 终止子进程及其派生的进程（例如 `npm start`、`cargo run` 启动的实际程序）
+async fn kill_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        // 子进程在独立的进程组中启动，进程组 id 等于子进程的 pid
+        #[cfg(unix)]
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+        #[cfg(windows)]
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .output()
+            .await;
+    }
+    let _ = child.kill().await;
+}
+
+/// 启动一次运行，立即返回 run id；输出和退出状态通过事件通知前端
+pub fn start_run(
+    app: AppHandle,
+    registry: &RunnerRegistry,
+    project_id: String,
+    cwd: PathBuf,
+    command: RunCommand,
+    timeout_secs: Option<u64>,
+) -> Result<String, String> {
+    let run_id = format!("run-{}", uuid::Uuid::new_v4());
+
+    let mut process = Command::new(&command.program);
+    process
+        .args(&command.args)
+        .current_dir(&cwd)
+        .env("PYTHONUNBUFFERED", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // 在独立的进程组中运行，停止时可以一起终止派生的进程
+    #[cfg(unix)]
+    process.process_group(0);
+    let mut child = process
+        .spawn()
+        .map_err(|e| format!("无法启动进程 `{}`: {}", command.display(), e))?;
+
+    let (stdin_tx, mut stdin_rx) = mpsc::unbounded_channel::<StdinMessage>();
+    let (kill_tx, kill_rx) = oneshot::channel::<()>();
+
+    registry.runs.lock().unwrap().insert(run_id.clone(), RunHandle {
+        stdin_tx,
+        kill_tx: Some(kill_tx),
+    });
+
+    // 标准输入转发
+    if let Some(mut stdin) = child.stdin.take() {
+        tokio::spawn(async move {
+            while let Some(Some(input)) = stdin_rx.recv().await {
+                if stdin.write_all(input.as_bytes()).await.is_err() {
+                    break;
+                }
+                let _ = stdin.flush().await;
+            }
+        });
+    }
+
+    let stdout_task = child.stdout.take().map(|stdout| {
+        tokio::spawn(pump_output(stdout, app.clone(), run_id.clone(), project_id.clone(), "stdout"))
+    });
+    let stderr_task = child.stderr.take().map(|stderr| {
+        tokio::spawn(pump_output(stderr, app.clone(), run_id.clone(), project_id.clone(), "stderr"))
+    });
+
+    let registry = registry.clone();
+    let task_run_id = run_id.clone();
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut timed_out = false;
+        let mut killed = false;
+
+        let status = tokio::select! {
+            status = child.wait() => status.ok(),
+            _ = kill_rx => {
+                killed = true;
+                kill_tree(&mut child).await;
+                child.wait().await.ok()
+            }
+            _ = tokio::time::sleep(timeout) => {
+                timed_out = true;
+                kill_tree(&mut child).await;
+                child.wait().await.ok()
+            }
+        };
+
+        // 等待输出转发完成，子进程派生的后台进程可能一直占用管道，因此限时等待
+        let mut captured = Vec::new();
+        let mut output_incomplete = false;
+        for task in [stdout_task, stderr_task] {
+            let output = match task {
+                Some(mut task) => match tokio::time::timeout(Duration::from_secs(2), &mut task).await {
+                    Ok(Ok(output)) => output,
+                    _ => {
+                        // 不再转发 run-exited 之后的输出
+                        task.abort();
+                        output_incomplete = true;
+                        String::new()
+                    }
+                },
+                None => String::new(),
+            };
+            captured.push(output);
+        }
+        let stderr = captured.pop().unwrap_or_default();
+        let stdout = captured.pop().unwrap_or_default();
+
+        let exit_code = status.and_then(|s| s.code());
+        let exit = RunExit {
+            run_id: task_run_id,
+            project_id,
+            command: command.display(),
+            exit_code,
+            success: !timed_out && !killed && exit_code == Some(0),
+            timed_out,
+            killed,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+
+        println!("▶ 运行结束: {} (exit: {:?})", exit.command, exit.exit_code);
+        registry.finish(RunRecord {
+            exit: exit.clone(),
+            stdout,
+            stderr,
+            output_incomplete,
+        });
+        let _ = app.emit("run-exited", exit);
+    });
+
+    Ok(run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_utf8_keeps_incomplete_char() {
+        // "你" = E4 BD A0，只读到前两个字节时应保留到下一次
+        let mut buffer = vec![b'a', 0xE4, 0xBD];
+        assert_eq!(take_utf8(&mut buffer), "a");
+        assert_eq!(buffer, vec![0xE4, 0xBD]);
+
+        buffer.push(0xA0);
+        assert_eq!(take_utf8(&mut buffer), "你");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_append_capped_keeps_tail() {
+        let mut captured = String::new();
+        append_capped(&mut captured, &"a".repeat(CAPTURE_LIMIT));
+        append_capped(&mut captured, "错误");
+        assert!(captured.len() <= CAPTURE_LIMIT);
+        assert!(captured.ends_with("错误"));
+    }
+
+    #[test]
+    fn test_command_for_file() {
+        let root = Path::new(".");
+        let cmd = command_for_file(root, "hello.py").unwrap();
+        assert_eq!(cmd.args, vec!["-u", "hello.py"]);
+
+        let cmd = command_for_file(root, "index.js").unwrap();
+        assert_eq!(cmd.display(), "node index.js");
+
+        assert!(command_for_file(root, "index.html").is_err());
+    }
+
+    #[test]
+    fn test_resolve_command_rejects_paths_outside_root() {
+        let root = Path::new(".");
+        assert!(resolve_command(root, "python", None, Some("../main.py")).unwrap_err().contains("无效"));
+        assert!(resolve_command(root, "python", None, Some("/etc/passwd")).unwrap_err().contains("无效"));
+    }
+}
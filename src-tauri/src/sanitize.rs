@@ -0,0 +1,198 @@
+// 需求文档整理：写入前去掉 AI 回复中的代码块标记和多余的说明文字，并检查必需的章节
+use crate::requirement::{RequirementDoc, SectionKind};
+use serde::{Deserialize, Serialize};
+
+/// 缺少章节时请 AI 修复的最大次数
+pub const MAX_FIX_ATTEMPTS: usize = 2;
+
+/// 需求文档必须包含的章节
+const REQUIRED_SECTIONS: [SectionKind; 3] = [
+    SectionKind::Description,
+    SectionKind::Functional,
+    SectionKind::TechStack,
+];
+
+/// 包裹整个文档的代码块可以使用的语言标记
+const WRAPPER_FENCE_TAGS: [&str; 3] = ["", "markdown", "md"];
+
+/// 文档末尾常见的客套话开头
+const TRAILER_PREFIXES: [&str; 12] = [
+    "希望", "如需", "如果您", "如果你", "如有", "以上是", "以上就是", "请告诉我", "let me know", "i hope", "hope this",
+    "feel free",
+];
+
+/// 客套话段落的最大长度（字符数）
+const MAX_TRAILER_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupReport {
+    /// 对 AI 输出做的修改
+    pub changes: Vec<String>,
+    /// 修复后仍然缺少的章节
+    pub missing_sections: Vec<String>,
+    /// 请 AI 修复的次数
+    pub fix_attempts: usize,
+}
+
+// ===== 整理 =====
+
+fn is_heading(line: &str) -> bool {
+    line.starts_with("# ") || line.starts_with("## ")
+}
+
+/// 包裹整个文档的代码块：第一个标题之前以 ```markdown 开始，到最后一个 ``` 结束
+fn unwrap_fence(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let open = lines.iter().position(|l| is_heading(l) || l.trim_start().starts_with("```"))?;
+    let tag = lines[open].trim().strip_prefix("```")?;
+    if !WRAPPER_FENCE_TAGS.contains(&tag.trim().to_lowercase().as_str()) {
+        return None;
+    }
+
+    let close = lines
+        .iter()
+        .rposition(|l| l.trim() == "```")
+        .filter(|close| *close > open)
+        .unwrap_or(lines.len());
+    Some(lines[open + 1..close].join("\n"))
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    if ["- ", "* ", "+ "].iter().any(|b| line.starts_with(b)) {
+        return true;
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+/// 去掉文档末尾的客套话段落
+///
+/// 只处理分隔线之后或不在任何章节中的简短段落，章节中的正文（例如以「如有」开头的待确认问题）保持不变
+fn strip_trailer(text: &str) -> Option<String> {
+    let trimmed = text.trim_end();
+    let start = trimmed.rfind("\n\n")? + 2;
+    let last = trimmed[start..].trim_start();
+    let lower = last.to_lowercase();
+    if last.starts_with('#')
+        || is_list_item(last)
+        || last.chars().count() > MAX_TRAILER_CHARS
+        || !TRAILER_PREFIXES.iter().any(|p| lower.starts_with(p))
+    {
+        return None;
+    }
+
+    let rest = trimmed[..start].trim_end();
+    let after_rule = rest.ends_with("---");
+    let in_section = rest.lines().any(|l| l.starts_with("## "));
+    if !after_rule && in_section {
+        return None;
+    }
+
+    // 客套话前的分隔线一并去掉
+    let rest = rest.strip_suffix("---").unwrap_or(rest).trim_end();
+    Some(rest.to_string())
+}
+
+/// 整理 AI 输出，返回整理后的内容和所做的修改
+pub fn clean(text: &str) -> (String, Vec<String>) {
+    let mut changes = Vec::new();
+    let mut content = text.replace("\r\n", "\n");
+
+    if let Some(inner) = unwrap_fence(&content) {
+        content = inner;
+        changes.push("去掉了包裹文档的代码块标记".to_string());
+    }
+
+    if let Some(pos) = content.lines().position(is_heading) {
+        if pos > 0 && content.lines().take(pos).any(|l| !l.trim().is_empty()) {
+            content = content.lines().skip(pos).collect::<Vec<_>>().join("\n");
+            changes.push("去掉了文档前的说明文字".to_string());
+        }
+    }
+
+    if let Some(rest) = strip_trailer(&content) {
+        content = rest;
+        changes.push("去掉了文档末尾的说明文字".to_string());
+    }
+
+    let content = format!("{}\n", content.trim());
+    if content.trim().is_empty() {
+        return (text.to_string(), Vec::new());
+    }
+    (content, changes)
+}
+
+// ===== 检查 =====
+
+/// 缺少的标题和章节
+pub fn missing_sections(content: &str) -> Vec<String> {
+    let doc = RequirementDoc::parse(content);
+    let present: Vec<SectionKind> = doc.model().sections.iter().map(|s| s.kind).collect();
+
+    let mut missing = Vec::new();
+    if doc.title().is_none() {
+        missing.push("一级标题（项目名称）".to_string());
+    }
+    for kind in REQUIRED_SECTIONS {
+        if !present.contains(&kind) {
+            missing.push(kind.default_heading().to_string());
+        }
+    }
+    missing
+}
+
+/// 构建请 AI 补充缺少章节的提示词
+pub fn build_fix_prompt(content: &str, missing: &[String]) -> String {
+    format!(
+        "你是 Code Sensei 的需求文档编辑助手。下面的需求文档缺少以下内容：{}
+
+## 当前需求文档内容
+```markdown
+{}
+```
+
+## 任务
+补充缺少的内容，已有内容保持不变。文档以「# 项目名称」开头，每个章节使用「## 」二级标题。
+
+请直接输出修改后的完整需求文档内容，不要使用代码块，不要有其他说明。",
+        missing.join("、"),
+        content.trim_end()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_strips_fence_and_chatter() {
+        let raw = "好的，以下是更新后的需求文档：\n\n```markdown\n# 记账本\n\n## 项目描述\n记录收支\n\n## 示例\n```python\nprint(1)\n```\n```\n\n希望对你有帮助！";
+        let (content, changes) = clean(raw);
+
+        assert_eq!(content, "# 记账本\n\n## 项目描述\n记录收支\n\n## 示例\n```python\nprint(1)\n```\n");
+        assert_eq!(changes.len(), 1);
+
+        let (content, changes) = clean("这是文档：\n# 记账本\n\n## 功能需求\n- 记账\n\n---\n\n如需调整请告诉我。");
+        assert_eq!(content, "# 记账本\n\n## 功能需求\n- 记账\n");
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn test_clean_keeps_section_text() {
+        let raw = "# 记账本\n\n## 功能需求\n- 记账\n\n## 待确认问题\n\n如有多个用户，是否需要分别记账？\n";
+        let (content, changes) = clean(raw);
+        assert_eq!(content, raw);
+        assert!(changes.is_empty());
+
+        let (content, _) = clean("# 记账本\n\n## 待确认问题\n\n- 如有多个用户，是否需要分别记账？\n\n---\n\n希望对你有帮助！");
+        assert_eq!(content, "# 记账本\n\n## 待确认问题\n\n- 如有多个用户，是否需要分别记账？\n");
+    }
+
+    #[test]
+    fn test_missing_sections() {
+        let missing = missing_sections("# 记账本\n\n## 项目描述\n记录收支\n\n## 功能需求\n- 记账\n");
+        assert_eq!(missing, vec![SectionKind::TechStack.default_heading().to_string()]);
+        assert_eq!(missing_sections("随便写写").len(), 4);
+    }
+}
@@ -0,0 +1,420 @@
+// 项目搜索：在项目目录中按文本或正则表达式搜索，结果按文件以事件流式返回
+use crate::ignore;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+/// 默认最多返回的匹配行数
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// 默认的上下文行数
+const DEFAULT_CONTEXT_LINES: usize = 2;
+
+/// 最多的上下文行数
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// 超过这个大小的文件不搜索
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// 匹配行超过这个长度时截断显示
+const MAX_LINE_CHARS: usize = 500;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub query: String,
+    /// 按正则表达式搜索，否则按普通文本搜索
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// 只搜索匹配这些 glob 的文件（相对路径），为空时搜索全部文件
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_results: Option<usize>,
+    pub context_lines: Option<usize>,
+}
+
+/// 一行中匹配的位置（按字符计算）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineMatch {
+    /// 从 1 开始的行号
+    pub line: usize,
+    pub text: String,
+    pub ranges: Vec<MatchRange>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub matches: Vec<LineMatch>,
+}
+
+/// 一个文件的搜索结果（`search-result` 事件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub search_id: String,
+    pub project_id: String,
+    pub file: FileMatches,
+}
+
+/// 搜索结束（`search-finished` 事件）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchSummary {
+    pub search_id: String,
+    pub project_id: String,
+    pub files_searched: usize,
+    pub files_matched: usize,
+    pub match_count: usize,
+    /// 达到结果上限且后面还有匹配，这些结果没有返回
+    pub truncated: bool,
+    pub cancelled: bool,
+    pub elapsed_ms: u64,
+}
+
+/// 编译好的搜索条件
+pub struct Matcher {
+    regex: Regex,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    max_results: usize,
+    context_lines: usize,
+}
+
+impl Matcher {
+    pub fn new(options: &SearchOptions) -> Result<Matcher, String> {
+        if options.query.is_empty() {
+            return Err("搜索内容不能为空".to_string());
+        }
+        let pattern = if options.regex {
+            options.query.clone()
+        } else {
+            regex::escape(&options.query)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(|e| format!("无效的正则表达式: {}", e))?;
+
+        Ok(Matcher {
+            regex,
+            include: build_globs(&options.include)?,
+            exclude: build_globs(&options.exclude)?,
+            max_results: options.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1),
+            context_lines: options.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES).min(MAX_CONTEXT_LINES),
+        })
+    }
+
+    /// 文件是否在搜索范围内
+    pub fn accepts(&self, relative_path: &str) -> bool {
+        if self.exclude.as_ref().map(|g| g.is_match(relative_path)).unwrap_or(false) {
+            return false;
+        }
+        self.include.as_ref().map(|g| g.is_match(relative_path)).unwrap_or(true)
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+fn build_globs(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    let patterns: Vec<&str> = patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // 不含目录的模式同时匹配任意目录下的文件，例如 *.py
+        builder.add(Glob::new(pattern).map_err(|e| format!("无效的文件匹配模式 {}: {}", pattern, e))?);
+        if !pattern.contains('/') {
+            let nested = format!("**/{}", pattern);
+            builder.add(Glob::new(&nested).map_err(|e| format!("无效的文件匹配模式 {}: {}", pattern, e))?);
+        }
+    }
+    builder.build().map(Some).map_err(|e| format!("无效的文件匹配模式: {}", e))
+}
+
+// ===== 搜索 =====
+
+/// 按文件树的顺序遍历可以搜索的文件，规则与文件树相同：跳过隐藏文件、常见的大型目录和二进制文件。
+/// `visit` 返回 `Break` 时停止遍历
+pub fn walk_files(
+    dir: &Path,
+    base: &Path,
+    visit: &mut impl FnMut(String, PathBuf) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Ok(entries) = fs::read_dir(dir) else { return ControlFlow::Continue(()) };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            walk_files(&path, base, visit)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            visit(relative.to_string_lossy().replace('\\', "/"), path)?;
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+/// 收集可以搜索的文件
+pub fn collect_files(dir: &Path, base: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let _ = walk_files(dir, base, &mut |relative, path| {
+        files.push((relative, path));
+        ControlFlow::Continue(())
+    });
+}
+
+/// 读取文本文件，二进制文件和过大的文件返回 None
+pub fn read_text(path: &Path) -> Option<String> {
+    if fs::metadata(path).map(|m| m.len() > MAX_FILE_BYTES).unwrap_or(true) {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if bytes.iter().take(8192).any(|b| *b == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn char_offset(line: &str, byte: usize) -> usize {
+    line[..byte].chars().count()
+}
+
+fn display_line(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_CHARS {
+        line.to_string()
+    } else {
+        line.chars().take(MAX_LINE_CHARS).collect::<String>() + "..."
+    }
+}
+
+/// 在一个文件中搜索，最多返回 `limit` 个匹配行
+pub fn search_text(matcher: &Matcher, content: &str, limit: usize) -> Vec<LineMatch> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut matches = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if matches.len() >= limit {
+            break;
+        }
+        let ranges: Vec<MatchRange> = matcher
+            .regex
+            .find_iter(line)
+            .filter(|m| m.end() > m.start())
+            .map(|m| MatchRange {
+                start: char_offset(line, m.start()),
+                end: char_offset(line, m.end()),
+            })
+            .collect();
+        if ranges.is_empty() {
+            continue;
+        }
+
+        let context = matcher.context_lines;
+        matches.push(LineMatch {
+            line: i + 1,
+            text: display_line(line),
+            ranges,
+            before: lines[i.saturating_sub(context)..i].iter().map(|l| display_line(l)).collect(),
+            after: lines[i + 1..(i + 1 + context).min(lines.len())]
+                .iter()
+                .map(|l| display_line(l))
+                .collect(),
+        });
+    }
+    matches
+}
+
+/// 边遍历边搜索整个目录，每找到一个有匹配的文件调用一次 `on_file`
+pub fn search_dir(
+    root: &Path,
+    matcher: &Matcher,
+    cancelled: &AtomicBool,
+    mut on_file: impl FnMut(FileMatches),
+) -> SearchSummary {
+    let started = Instant::now();
+    let mut summary = SearchSummary::default();
+
+    let _ = walk_files(root, root, &mut |relative, path| {
+        if cancelled.load(Ordering::Relaxed) {
+            summary.cancelled = true;
+            return ControlFlow::Break(());
+        }
+        if !matcher.accepts(&relative) {
+            return ControlFlow::Continue(());
+        }
+        let Some(content) = read_text(&path) else { return ControlFlow::Continue(()) };
+
+        // 结果已满，只需要确认后面是否还有匹配
+        let remaining = matcher.max_results - summary.match_count;
+        if remaining == 0 {
+            if search_text(matcher, &content, 1).is_empty() {
+                return ControlFlow::Continue(());
+            }
+            summary.truncated = true;
+            return ControlFlow::Break(());
+        }
+        summary.files_searched += 1;
+
+        // 多取一个匹配，用来判断这个文件中是否还有没返回的结果
+        let mut matches = search_text(matcher, &content, remaining + 1);
+        if matches.len() > remaining {
+            matches.truncate(remaining);
+            summary.truncated = true;
+        }
+        if matches.is_empty() {
+            return ControlFlow::Continue(());
+        }
+        summary.files_matched += 1;
+        summary.match_count += matches.len();
+        on_file(FileMatches { path: relative, matches });
+
+        if summary.truncated {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    summary.elapsed_ms = started.elapsed().as_millis() as u64;
+    summary
+}
+
+// ===== 后台搜索 =====
+
+/// 正在进行的搜索，用于取消
+#[derive(Clone, Default)]
+pub struct SearchRegistry {
+    searches: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl SearchRegistry {
+    pub fn cancel(&self, search_id: &str) -> Result<(), String> {
+        let searches = self.searches.lock().unwrap();
+        let flag = searches
+            .get(search_id)
+            .ok_or_else(|| format!("搜索不存在或已结束: {}", search_id))?;
+        flag.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// 在后台开始搜索，立即返回 search id，结果通过 `search-result` 和 `search-finished` 事件推送
+pub fn start_search(
+    app: AppHandle,
+    registry: &SearchRegistry,
+    project_id: String,
+    root: PathBuf,
+    options: &SearchOptions,
+) -> Result<String, String> {
+    let matcher = Matcher::new(options)?;
+    let search_id = format!("search-{}", uuid::Uuid::new_v4());
+    let cancelled = Arc::new(AtomicBool::new(false));
+    registry
+        .searches
+        .lock()
+        .unwrap()
+        .insert(search_id.clone(), cancelled.clone());
+
+    let searches = registry.searches.clone();
+    let task_search_id = search_id.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut summary = search_dir(&root, &matcher, &cancelled, |file| {
+            let _ = app.emit("search-result", SearchResult {
+                search_id: task_search_id.clone(),
+                project_id: project_id.clone(),
+                file,
+            });
+        });
+        searches.lock().unwrap().remove(&task_search_id);
+
+        summary.search_id = task_search_id;
+        summary.project_id = project_id;
+        let _ = app.emit("search-finished", summary);
+    });
+
+    Ok(search_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(query: &str) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_text_with_context() {
+        let matcher = Matcher::new(&SearchOptions {
+            context_lines: Some(1),
+            ..options("total")
+        })
+        .unwrap();
+        let matches = search_text(&matcher, "a = 1\n总计 Total = a + TOTAL\nprint(a)\n", 10);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 2);
+        assert_eq!(matches[0].ranges, vec![MatchRange { start: 3, end: 8 }, MatchRange { start: 15, end: 20 }]);
+        assert_eq!(matches[0].before, vec!["a = 1"]);
+        assert_eq!(matches[0].after, vec!["print(a)"]);
+
+        let regex = Matcher::new(&SearchOptions { regex: true, case_sensitive: true, ..options(r"T\w+L") }).unwrap();
+        assert_eq!(search_text(&regex, "Total TOTAL", 10)[0].ranges, vec![MatchRange { start: 6, end: 11 }]);
+        assert!(Matcher::new(&SearchOptions { regex: true, ..options("(") }).is_err());
+    }
+
+    #[test]
+    fn test_search_dir_respects_rules() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-search-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+        fs::write(dir.join("src/main.py"), "def hello():\n    pass\n").unwrap();
+        fs::write(dir.join("src/notes.txt"), "hello\nhello\n").unwrap();
+        fs::write(dir.join("node_modules/pkg/index.js"), "hello").unwrap();
+        fs::write(dir.join(".secret"), "hello").unwrap();
+
+        let matcher = Matcher::new(&SearchOptions { include: vec!["*.py".to_string()], ..options("hello") }).unwrap();
+        let mut found = Vec::new();
+        let summary = search_dir(&dir, &matcher, &AtomicBool::new(false), |f| found.push(f.path));
+
+        let capped = Matcher::new(&SearchOptions { max_results: Some(2), ..options("hello") }).unwrap();
+        let capped_summary = search_dir(&dir, &capped, &AtomicBool::new(false), |_| {});
+        // 匹配数正好等于上限时不算截断
+        let exact = Matcher::new(&SearchOptions { max_results: Some(3), ..options("hello") }).unwrap();
+        let exact_summary = search_dir(&dir, &exact, &AtomicBool::new(false), |_| {});
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, vec!["src/main.py"]);
+        assert_eq!(summary.match_count, 1);
+        assert_eq!(capped_summary.match_count, 2);
+        assert!(capped_summary.truncated);
+        assert_eq!(exact_summary.match_count, 3);
+        assert!(!exact_summary.truncated);
+    }
+}
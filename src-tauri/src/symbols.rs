@@ -0,0 +1,575 @@
+// 符号索引：从 Python、Rust、JavaScript/TypeScript 和 Java 源文件中提取函数、类和方法及其行号范围
+use crate::trace;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+/// 符号索引保存的文件名（位于项目目录）
+const INDEX_FILE: &str = "symbols.json";
+
+/// 声明的参数列表跨多行时，最多向后查找左花括号的行数
+const SIGNATURE_LINES: usize = 5;
+
+/// 可能被误认为方法名的关键字
+const KEYWORDS: [&str; 12] = [
+    "if", "for", "while", "switch", "catch", "return", "function", "new", "else", "throw", "super", "this",
+];
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Struct,
+    Enum,
+    Trait,
+    Interface,
+    Impl,
+    Module,
+}
+
+impl SymbolKind {
+    /// 可以包含方法的符号
+    fn is_container(self) -> bool {
+        matches!(
+            self,
+            SymbolKind::Class | SymbolKind::Trait | SymbolKind::Interface | SymbolKind::Impl | SymbolKind::Enum
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 起止行号（从 1 开始，包含结束行）
+    pub line: usize,
+    pub end_line: usize,
+    /// 所属的类、impl 或 trait
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    Python,
+    Rust,
+    Script,
+    Java,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSymbols {
+    /// 解析时文件的修改时间（毫秒）和大小，用于增量更新
+    pub modified: i64,
+    pub size: u64,
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    pub files: BTreeMap<String, FileSymbols>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    pub path: String,
+    pub symbol: Symbol,
+}
+
+fn lang_for_path(path: &str) -> Option<Lang> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "py" | "pyw" => Some(Lang::Python),
+        "rs" => Some(Lang::Rust),
+        "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" | "mts" | "cts" => Some(Lang::Script),
+        "java" => Some(Lang::Java),
+        _ => None,
+    }
+}
+
+pub fn is_supported(path: &str) -> bool {
+    lang_for_path(path).is_some()
+}
+
+// ===== 提取 =====
+
+fn regexes(patterns: &[(&str, SymbolKind)]) -> Vec<(Regex, SymbolKind)> {
+    patterns
+        .iter()
+        .map(|(pattern, kind)| (Regex::new(pattern).unwrap(), *kind))
+        .collect()
+}
+
+/// 顶层或嵌套的声明
+fn declaration_patterns(lang: Lang) -> &'static [(Regex, SymbolKind)] {
+    static RUST: OnceLock<Vec<(Regex, SymbolKind)>> = OnceLock::new();
+    static SCRIPT: OnceLock<Vec<(Regex, SymbolKind)>> = OnceLock::new();
+    static JAVA: OnceLock<Vec<(Regex, SymbolKind)>> = OnceLock::new();
+
+    match lang {
+        Lang::Rust => RUST.get_or_init(|| {
+            let vis = r"^(?:pub(?:\([^)]*\))?\s+)?";
+            regexes(&[
+                (&format!(r#"{}(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+([A-Za-z_]\w*)"#, vis), SymbolKind::Function),
+                (&format!(r"{}struct\s+([A-Za-z_]\w*)", vis), SymbolKind::Struct),
+                (&format!(r"{}enum\s+([A-Za-z_]\w*)", vis), SymbolKind::Enum),
+                (&format!(r"{}(?:unsafe\s+)?trait\s+([A-Za-z_]\w*)", vis), SymbolKind::Trait),
+                (&format!(r"{}mod\s+([A-Za-z_]\w*)\s*\{{", vis), SymbolKind::Module),
+                (r"^(?:unsafe\s+)?impl\b(.*)$", SymbolKind::Impl),
+            ])
+        }),
+        Lang::Script => SCRIPT.get_or_init(|| {
+            regexes(&[
+                (r"^(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)", SymbolKind::Function),
+                (r"^(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)", SymbolKind::Class),
+                (r"^(?:export\s+)?interface\s+([A-Za-z_$][\w$]*)", SymbolKind::Interface),
+                (r"^(?:export\s+)?(?:const\s+)?enum\s+([A-Za-z_$][\w$]*)", SymbolKind::Enum),
+                (
+                    r"^(?:export\s+)?(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[A-Za-z_$][\w$]*\s*=>)",
+                    SymbolKind::Function,
+                ),
+            ])
+        }),
+        Lang::Java => JAVA.get_or_init(|| {
+            let modifiers = r"^(?:(?:public|private|protected|static|final|abstract|sealed|non-sealed|strictfp)\s+)*";
+            regexes(&[
+                (&format!(r"{}(?:class|record)\s+([A-Za-z_$][\w$]*)", modifiers), SymbolKind::Class),
+                (&format!(r"{}@?interface\s+([A-Za-z_$][\w$]*)", modifiers), SymbolKind::Interface),
+                (&format!(r"{}enum\s+([A-Za-z_$][\w$]*)", modifiers), SymbolKind::Enum),
+            ])
+        }),
+        Lang::Python => &[],
+    }
+}
+
+/// 类体中的方法声明（只在类体的第一层匹配）
+fn method_pattern(lang: Lang) -> Option<&'static Regex> {
+    static SCRIPT: OnceLock<Regex> = OnceLock::new();
+    static JAVA: OnceLock<Regex> = OnceLock::new();
+    match lang {
+        Lang::Script => Some(SCRIPT.get_or_init(|| {
+            Regex::new(r"^(?:(?:public|private|protected|static|async|get|set|override|readonly|abstract)\s+)*\*?\s*(#?[A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\(").unwrap()
+        })),
+        Lang::Java => Some(JAVA.get_or_init(|| {
+            Regex::new(r"^(?:(?:public|private|protected|static|final|abstract|synchronized|native|default|strictfp)\s+)*(?:<[^>]+>\s+)?(?:[\w$.<>\[\],?]+\s+)?([A-Za-z_$][\w$]*)\s*\(").unwrap()
+        })),
+        _ => None,
+    }
+}
+
+/// impl 块的名称：去掉泛型参数、where 子句和花括号，例如 `Display for Point`
+fn impl_name(rest: &str) -> Option<String> {
+    let mut rest = rest.trim();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        let end = rest.char_indices().find_map(|(i, c)| {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(i)
+        })?;
+        rest = rest[end + 1..].trim();
+    }
+    let rest = rest.split(" where").next().unwrap_or(rest);
+    let name = rest.trim_end_matches('{').trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// 去掉注释和字符串字面量，只保留用于匹配声明和花括号的代码
+fn strip_code(line: &str, lang: Lang, in_block_comment: &mut bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if *in_block_comment {
+            if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                *in_block_comment = false;
+                i += 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        match chars[i] {
+            '/' if chars.get(i + 1) == Some(&'/') => break,
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                *in_block_comment = true;
+                i += 2;
+            }
+            // Rust 的单引号也用于生命周期，只把 'x' 和 '\x' 当作字符字面量
+            '\'' if lang == Lang::Rust && chars.get(i + 2) != Some(&'\'') && chars.get(i + 1) != Some(&'\\') => {
+                out.push('\'');
+                i += 1;
+            }
+            quote @ ('"' | '\'' | '`') => {
+                out.push(quote);
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                out.push(quote);
+                i += 1;
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// 使用花括号的语言：按声明识别符号，按花括号配对确定结束行
+fn extract_braced(content: &str, lang: Lang) -> Vec<Symbol> {
+    struct Open {
+        symbol: usize,
+        depth: i32,
+    }
+
+    let mut symbols: Vec<Symbol> = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    let mut depth = 0;
+    let mut pending: Option<(usize, usize)> = None;
+    let mut in_block_comment = false;
+
+    for (i, line) in content.lines().enumerate() {
+        let code = strip_code(line, lang, &mut in_block_comment);
+        let trimmed = code.trim();
+
+        let container = open.last().filter(|o| symbols[o.symbol].kind.is_container());
+        let in_container_body = container.map(|o| o.depth == depth).unwrap_or(false);
+        let container_name = container.map(|o| symbols[o.symbol].name.clone());
+
+        let mut found = declaration_patterns(lang).iter().find_map(|(regex, kind)| {
+            let caps = regex.captures(trimmed)?;
+            let name = match kind {
+                SymbolKind::Impl => impl_name(caps.get(1)?.as_str())?,
+                _ => caps.get(1)?.as_str().to_string(),
+            };
+            let kind = match kind {
+                SymbolKind::Function if in_container_body => SymbolKind::Method,
+                kind => *kind,
+            };
+            Some((name, kind))
+        });
+        if found.is_none() && in_container_body {
+            found = method_pattern(lang)
+                .and_then(|regex| regex.captures(trimmed))
+                .map(|caps| caps[1].to_string())
+                .filter(|name| !KEYWORDS.contains(&name.as_str()))
+                .filter(|_| !trimmed.split('(').next().unwrap_or("").contains('='))
+                .map(|name| (name, SymbolKind::Method));
+        }
+
+        if let Some((name, kind)) = found {
+            symbols.push(Symbol {
+                name,
+                kind,
+                line: i + 1,
+                end_line: i + 1,
+                container: container_name.filter(|_| in_container_body),
+            });
+            pending = Some((symbols.len() - 1, i));
+        }
+
+        for c in trimmed.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    if let Some((symbol, _)) = pending.take() {
+                        open.push(Open { symbol, depth });
+                    }
+                }
+                '}' => {
+                    if open.last().map(|o| o.depth == depth).unwrap_or(false) {
+                        let o = open.pop().unwrap();
+                        symbols[o.symbol].end_line = i + 1;
+                    }
+                    depth -= 1;
+                }
+                // 没有函数体的声明，例如 trait 中的方法签名
+                ';' => pending = None,
+                _ => {}
+            }
+        }
+        if pending.map(|(_, start)| i >= start + SIGNATURE_LINES).unwrap_or(false) {
+            pending = None;
+        }
+    }
+    symbols
+}
+
+/// Python：按缩进确定符号的范围
+fn extract_python(content: &str) -> Vec<Symbol> {
+    static DEF: OnceLock<Regex> = OnceLock::new();
+    let def = DEF.get_or_init(|| Regex::new(r"^(\s*)(?:async\s+)?(def|class)\s+([A-Za-z_]\w*)").unwrap());
+
+    let mut symbols: Vec<Symbol> = Vec::new();
+    // (缩进, 符号下标)
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_code_line = 0;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        while open.last().map(|(o, _)| indent <= *o).unwrap_or(false) {
+            let (_, symbol) = open.pop().unwrap();
+            symbols[symbol].end_line = last_code_line;
+        }
+
+        if let Some(caps) = def.captures(line) {
+            let parent = open.last().map(|(_, s)| &symbols[*s]);
+            let in_class = parent.map(|p| p.kind == SymbolKind::Class).unwrap_or(false);
+            let kind = match &caps[2] {
+                "class" => SymbolKind::Class,
+                _ if in_class => SymbolKind::Method,
+                _ => SymbolKind::Function,
+            };
+            symbols.push(Symbol {
+                name: caps[3].to_string(),
+                kind,
+                line: i + 1,
+                end_line: i + 1,
+                container: parent.filter(|_| in_class).map(|p| p.name.clone()),
+            });
+            open.push((indent, symbols.len() - 1));
+        }
+        last_code_line = i + 1;
+    }
+    for (_, symbol) in open {
+        symbols[symbol].end_line = last_code_line;
+    }
+    symbols
+}
+
+/// 提取文件中的符号，不支持的文件类型返回 None
+pub fn extract(path: &str, content: &str) -> Option<Vec<Symbol>> {
+    Some(match lang_for_path(path)? {
+        Lang::Python => extract_python(content),
+        lang => extract_braced(content, lang),
+    })
+}
+
+// ===== 索引 =====
+
+fn file_stamp(path: &Path) -> Option<(i64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some((modified, metadata.len()))
+}
+
+fn parse_file(root: &Path, relative: &str) -> Option<FileSymbols> {
+    let path = root.join(relative);
+    let (modified, size) = file_stamp(&path)?;
+    let content = fs::read_to_string(&path).ok()?;
+    Some(FileSymbols {
+        modified,
+        size,
+        symbols: extract(relative, &content)?,
+    })
+}
+
+pub fn load_index(project_dir: &Path) -> SymbolIndex {
+    fs::read_to_string(project_dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(project_dir: &Path, index: &SymbolIndex) -> Result<(), String> {
+    let content = serde_json::to_string(index).map_err(|e| format!("无法序列化符号索引: {}", e))?;
+    fs::write(project_dir.join(INDEX_FILE), content).map_err(|e| format!("无法保存符号索引: {}", e))
+}
+
+/// 更新索引：只重新解析修改时间或大小变化的文件，并去掉已删除的文件
+pub fn refresh(project_dir: &Path, root: &Path) -> SymbolIndex {
+    let mut index = load_index(project_dir);
+    let mut files = Vec::new();
+    trace::collect_code_files(root, root, &mut files);
+    files.retain(|f| is_supported(f));
+
+    let before = index.files.len();
+    let present: HashSet<&String> = files.iter().collect();
+    index.files.retain(|path, _| present.contains(path));
+    let mut changed = index.files.len() != before;
+
+    for file in files {
+        let stamp = file_stamp(&root.join(&file));
+        let fresh = index
+            .files
+            .get(&file)
+            .map(|f| Some((f.modified, f.size)) == stamp)
+            .unwrap_or(false);
+        if fresh {
+            continue;
+        }
+        if let Some(symbols) = parse_file(root, &file) {
+            index.files.insert(file, symbols);
+            changed = true;
+        }
+    }
+
+    if changed {
+        if let Err(e) = save_index(project_dir, &index) {
+            eprintln!("{}", e);
+        }
+    }
+    index
+}
+
+/// 文件被保存或修改后更新这些文件的索引
+pub fn update_files(project_dir: &Path, root: &Path, files: &[String]) -> Result<(), String> {
+    let files: Vec<&String> = files.iter().filter(|f| is_supported(f)).collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+    let mut index = load_index(project_dir);
+    for file in files {
+        let relative = file.replace('\\', "/");
+        match parse_file(root, &relative) {
+            Some(symbols) => index.files.insert(relative, symbols),
+            None => index.files.remove(&relative),
+        };
+    }
+    save_index(project_dir, &index)
+}
+
+/// 按名称查找符号：完全匹配优先，其次是前缀匹配，最后是包含匹配（不区分大小写）
+pub fn find(index: &SymbolIndex, query: &str, limit: usize) -> Vec<SymbolMatch> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(usize, SymbolMatch)> = index
+        .files
+        .iter()
+        .flat_map(|(path, file)| file.symbols.iter().map(move |s| (path, s)))
+        .filter_map(|(path, symbol)| {
+            let name = symbol.name.to_lowercase();
+            let rank = if name == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else if name.contains(&query) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, SymbolMatch { path: path.clone(), symbol: symbol.clone() }))
+        })
+        .collect();
+    matches.sort_by(|(ra, a), (rb, b)| {
+        ra.cmp(rb)
+            .then(a.symbol.name.len().cmp(&b.symbol.name.len()))
+            .then(a.path.cmp(&b.path))
+            .then(a.symbol.line.cmp(&b.symbol.line))
+    });
+    matches.into_iter().take(limit).map(|(_, m)| m).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(symbols: &[Symbol]) -> Vec<(String, SymbolKind, usize, usize, Option<String>)> {
+        symbols
+            .iter()
+            .map(|s| (s.name.clone(), s.kind, s.line, s.end_line, s.container.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_python() {
+        let source = "import os\n\nclass Calc:\n    def add(self, a, b):\n        return a + b\n\n    async def sub(self, a, b):\n        return a - b\n\ndef main():\n    print(Calc().add(1, 2))\n";
+        let symbols = extract("calc.py", source).unwrap();
+        assert_eq!(
+            summary(&symbols),
+            vec![
+                ("Calc".to_string(), SymbolKind::Class, 3, 8, None),
+                ("add".to_string(), SymbolKind::Method, 4, 5, Some("Calc".to_string())),
+                ("sub".to_string(), SymbolKind::Method, 7, 8, Some("Calc".to_string())),
+                ("main".to_string(), SymbolKind::Function, 10, 11, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_braced_languages() {
+        let rust = "pub struct Point { x: i32 }\n\nimpl<T: Into<String>> Display for Point {\n    fn fmt(&self) -> &'static str {\n        \"}\"\n    }\n}\n\npub trait Shape {\n    fn area(&self) -> f64;\n}\n\nfn main() {\n    let c = '{';\n}\n";
+        assert_eq!(
+            summary(&extract("main.rs", rust).unwrap()),
+            vec![
+                ("Point".to_string(), SymbolKind::Struct, 1, 1, None),
+                ("Display for Point".to_string(), SymbolKind::Impl, 3, 7, None),
+                ("fmt".to_string(), SymbolKind::Method, 4, 6, Some("Display for Point".to_string())),
+                ("Shape".to_string(), SymbolKind::Trait, 9, 11, None),
+                ("area".to_string(), SymbolKind::Method, 10, 10, Some("Shape".to_string())),
+                ("main".to_string(), SymbolKind::Function, 13, 15, None),
+            ]
+        );
+
+        let js = "export class Cart {\n  constructor() {\n    this.items = []\n  }\n  add(item) {\n    if (item) {\n      this.items.push(item)\n    }\n  }\n}\n\nconst total = (items) => {\n  return items.length\n}\n";
+        assert_eq!(
+            summary(&extract("cart.js", js).unwrap()),
+            vec![
+                ("Cart".to_string(), SymbolKind::Class, 1, 10, None),
+                ("constructor".to_string(), SymbolKind::Method, 2, 4, Some("Cart".to_string())),
+                ("add".to_string(), SymbolKind::Method, 5, 9, Some("Cart".to_string())),
+                ("total".to_string(), SymbolKind::Function, 12, 14, None),
+            ]
+        );
+
+        let java = "public class Main {\n    private int count = compute();\n\n    public Main() {\n    }\n\n    public static void main(String[] args) {\n        for (int i = 0; i < 3; i++) {\n        }\n    }\n}\n";
+        assert_eq!(
+            summary(&extract("Main.java", java).unwrap()),
+            vec![
+                ("Main".to_string(), SymbolKind::Class, 1, 11, None),
+                ("Main".to_string(), SymbolKind::Method, 4, 5, Some("Main".to_string())),
+                ("main".to_string(), SymbolKind::Method, 7, 10, Some("Main".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_updates_incrementally() {
+        let base = std::env::temp_dir().join(format!("code-sensei-symbols-{}", std::process::id()));
+        let root = base.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.py"), "def alpha():\n    pass\n").unwrap();
+        fs::write(root.join("notes.txt"), "def nope():\n").unwrap();
+
+        let index = refresh(&base, &root);
+        fs::write(root.join("a.py"), "def alpha_two():\n    pass\n").unwrap();
+        update_files(&base, &root, &["a.py".to_string()]).unwrap();
+        let updated = load_index(&base);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(index.files.len(), 1);
+        assert_eq!(find(&index, "ALPHA", 10)[0].symbol.name, "alpha");
+        let found = find(&updated, "alpha", 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].symbol.name, "alpha_two");
+    }
+}
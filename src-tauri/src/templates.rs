@@ -0,0 +1,573 @@
+// 项目模板：内置的入门模板和用户保存的自定义模板
+use crate::requirement::SectionKind;
+use crate::{ignore, Project};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path};
+
+/// 保存为模板时最多收录的文件数
+const MAX_TEMPLATE_FILES: usize = 200;
+
+/// 保存为模板时单个文件的最大字节数
+const MAX_TEMPLATE_FILE_SIZE: u64 = 256 * 1024;
+
+/// 不使用模板时的 id（只创建空的 src/ 和 docs/）
+pub const EMPTY_TEMPLATE_ID: &str = "empty";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateFile {
+    /// 相对于项目代码目录的路径
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub language: String,
+    /// 运行项目的命令
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_command: Option<String>,
+    /// 运行测试的命令
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_command: Option<String>,
+    /// 需求文档骨架，`{{name}}` 和 `{{description}}` 会被替换
+    pub requirement: String,
+    pub files: Vec<TemplateFile>,
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+/// 模板列表中的摘要信息（不含文件内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateSummary {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_command: Option<String>,
+    pub file_count: usize,
+    pub builtin: bool,
+}
+
+impl ProjectTemplate {
+    pub fn summary(&self) -> TemplateSummary {
+        TemplateSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            language: self.language.clone(),
+            run_command: self.run_command.clone(),
+            test_command: self.test_command.clone(),
+            file_count: self.files.len(),
+            builtin: self.builtin,
+        }
+    }
+
+    /// 渲染需求文档骨架
+    pub fn render_requirement(&self, name: &str, description: &str) -> String {
+        self.requirement
+            .replace("{{name}}", name)
+            .replace("{{description}}", description)
+    }
+}
+
+fn file(path: &str, content: &str) -> TemplateFile {
+    TemplateFile {
+        path: path.to_string(),
+        content: content.to_string(),
+    }
+}
+
+/// 通用的需求文档骨架
+fn requirement_skeleton(features: &[&str], tech_stack: &[&str]) -> String {
+    let features = features
+        .iter()
+        .enumerate()
+        .map(|(i, f)| format!("{}. {}", i + 1, f))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let tech_stack = tech_stack
+        .iter()
+        .map(|t| format!("- {}", t))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "# {{{{name}}}} 需求文档\n\n## 项目描述\n{{{{description}}}}\n\n## 功能需求\n{}\n\n## 技术栈\n{}\n\n## 验收标准\n<!-- 每行一条可以检验的标准，例如：- 运行程序后能看到问候语 -->\n",
+        features, tech_stack
+    )
+}
+
+// ===== 内置模板 =====
+
+fn python_cli() -> ProjectTemplate {
+    ProjectTemplate {
+        id: "python-cli".to_string(),
+        name: "Python 命令行程序".to_string(),
+        description: "带参数解析和 pytest 测试的 Python 命令行程序".to_string(),
+        language: "Python".to_string(),
+        run_command: Some("python3 main.py".to_string()),
+        test_command: Some("python3 -m pytest".to_string()),
+        requirement: requirement_skeleton(
+            &["通过命令行参数接收输入", "输出处理结果"],
+            &["Python 3", "argparse", "pytest"],
+        ),
+        files: vec![
+            file("main.py", r#"import argparse
+
+
+def greet(name: str) -> str:
+    """返回问候语"""
+    return f"你好，{name}！"
+
+
+def main() -> None:
+    parser = argparse.ArgumentParser(description="命令行程序示例")
+    parser.add_argument("name", nargs="?", default="世界", help="要问候的名字")
+    args = parser.parse_args()
+    print(greet(args.name))
+
+
+if __name__ == "__main__":
+    main()
+"#),
+            file("tests/test_main.py", r#"from main import greet
+
+
+def test_greet():
+    assert greet("Sensei") == "你好，Sensei！"
+"#),
+            file("requirements.txt", "pytest\n"),
+            file(".gitignore", "__pycache__/\n.venv/\n.pytest_cache/\n"),
+        ],
+        builtin: true,
+    }
+}
+
+fn rust_bin() -> ProjectTemplate {
+    ProjectTemplate {
+        id: "rust-bin".to_string(),
+        name: "Rust 可执行程序".to_string(),
+        description: "使用 Cargo 管理的 Rust 二进制 crate，包含单元测试".to_string(),
+        language: "Rust".to_string(),
+        run_command: Some("cargo run".to_string()),
+        test_command: Some("cargo test".to_string()),
+        requirement: requirement_skeleton(
+            &["程序启动后完成主要功能", "对错误输入给出友好提示"],
+            &["Rust (edition 2021)", "Cargo"],
+        ),
+        files: vec![
+            file("Cargo.toml", r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#),
+            file("src/main.rs", r#"fn greet(name: &str) -> String {
+    format!("你好，{}！", name)
+}
+
+fn main() {
+    println!("{}", greet("世界"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greet() {
+        assert_eq!(greet("Sensei"), "你好，Sensei！");
+    }
+}
+"#),
+            file(".gitignore", "/target\n"),
+        ],
+        builtin: true,
+    }
+}
+
+fn node_express() -> ProjectTemplate {
+    ProjectTemplate {
+        id: "node-express".to_string(),
+        name: "Node.js Express API".to_string(),
+        description: "基于 Express 的 REST API 服务，使用 jest 和 supertest 测试".to_string(),
+        language: "JavaScript".to_string(),
+        run_command: Some("npm start".to_string()),
+        test_command: Some("npm test".to_string()),
+        requirement: requirement_skeleton(
+            &["提供 REST API 接口", "返回 JSON 格式的数据"],
+            &["Node.js", "Express", "jest", "supertest"],
+        ),
+        files: vec![
+            file("package.json", r#"{
+  "name": "app",
+  "version": "0.1.0",
+  "main": "index.js",
+  "scripts": {
+    "start": "node index.js",
+    "test": "jest"
+  },
+  "dependencies": {
+    "express": "^4.19.2"
+  },
+  "devDependencies": {
+    "jest": "^29.7.0",
+    "supertest": "^7.0.0"
+  }
+}
+"#),
+            file("app.js", r#"const express = require('express')
+
+const app = express()
+app.use(express.json())
+
+app.get('/api/hello', (req, res) => {
+  res.json({ message: '你好，世界！' })
+})
+
+module.exports = app
+"#),
+            file("index.js", r#"const app = require('./app')
+
+const port = process.env.PORT || 3000
+
+app.listen(port, () => {
+  console.log(`服务已启动: http://localhost:${port}`)
+})
+"#),
+            file("app.test.js", r#"const request = require('supertest')
+const app = require('./app')
+
+test('GET /api/hello 返回问候语', async () => {
+  const res = await request(app).get('/api/hello')
+  expect(res.status).toBe(200)
+  expect(res.body.message).toBe('你好，世界！')
+})
+"#),
+            file(".gitignore", "node_modules/\ncoverage/\n"),
+        ],
+        builtin: true,
+    }
+}
+
+fn static_web() -> ProjectTemplate {
+    ProjectTemplate {
+        id: "static-web".to_string(),
+        name: "静态网页".to_string(),
+        description: "HTML + CSS + JavaScript 的静态网页".to_string(),
+        language: "JavaScript".to_string(),
+        run_command: Some("python3 -m http.server 8000".to_string()),
+        test_command: Some("npx --yes jest".to_string()),
+        requirement: requirement_skeleton(
+            &["页面展示主要内容", "页面在手机和电脑上都能正常显示"],
+            &["HTML5", "CSS3", "JavaScript", "jest"],
+        ),
+        files: vec![
+            file("index.html", r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+  <meta charset="UTF-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  <title>我的网页</title>
+  <link rel="stylesheet" href="style.css">
+</head>
+<body>
+  <h1>你好，世界！</h1>
+  <button id="greet">点我</button>
+  <script src="script.js"></script>
+</body>
+</html>
+"#),
+            file("style.css", r#"body {
+  font-family: sans-serif;
+  max-width: 720px;
+  margin: 40px auto;
+  padding: 0 16px;
+}
+"#),
+            file("script.js", r#"function greet(name) {
+  return `你好，${name}！`
+}
+
+// 在浏览器中绑定按钮，在 jest 中导出函数供测试使用
+if (typeof document !== 'undefined') {
+  document.getElementById('greet').addEventListener('click', () => {
+    alert(greet('世界'))
+  })
+}
+
+if (typeof module !== 'undefined') {
+  module.exports = { greet }
+}
+"#),
+            file("script.test.js", r#"const { greet } = require('./script')
+
+test('greet 返回问候语', () => {
+  expect(greet('世界')).toBe('你好，世界！')
+})
+"#),
+            file("package.json", r#"{
+  "name": "web",
+  "version": "0.1.0",
+  "private": true,
+  "scripts": {
+    "test": "jest"
+  },
+  "devDependencies": {
+    "jest": "^29.7.0"
+  }
+}
+"#),
+        ],
+        builtin: true,
+    }
+}
+
+/// 所有内置模板
+pub fn builtin_templates() -> Vec<ProjectTemplate> {
+    vec![python_cli(), rust_bin(), node_express(), static_web()]
+}
+
+// ===== 用户模板 =====
+
+/// 读取用户保存的模板
+fn user_templates(templates_dir: &Path) -> Vec<ProjectTemplate> {
+    let Ok(entries) = fs::read_dir(templates_dir) else { return Vec::new() };
+
+    let mut templates: Vec<ProjectTemplate> = entries
+        .flatten()
+        .filter(|e| e.path().extension().map(|ext| ext == "json").unwrap_or(false))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|content| serde_json::from_str::<ProjectTemplate>(&content).ok())
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// 列出所有模板，指定语言时该语言的模板排在前面
+pub fn list_templates(templates_dir: &Path, language: Option<&str>) -> Vec<TemplateSummary> {
+    let mut templates: Vec<TemplateSummary> = builtin_templates()
+        .iter()
+        .chain(user_templates(templates_dir).iter())
+        .map(|t| t.summary())
+        .collect();
+
+    if let Some(language) = language {
+        templates.sort_by_key(|t| !t.language.eq_ignore_ascii_case(language));
+    }
+    templates
+}
+
+/// 按 id 查找模板
+pub fn find_template(templates_dir: &Path, template_id: &str) -> Result<ProjectTemplate, String> {
+    builtin_templates()
+        .into_iter()
+        .chain(user_templates(templates_dir))
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| format!("模板不存在: {}", template_id))
+}
+
+/// 模板 id 只能包含字母、数字、`-` 和 `_`，避免拼接成模板目录之外的路径
+fn is_valid_id(template_id: &str) -> bool {
+    !template_id.is_empty() && template_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 模板文件的路径必须是项目内的相对路径
+fn is_relative_path(path: &str) -> bool {
+    !path.is_empty() && Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// 把模板文件写入项目代码目录，已存在的文件不会被覆盖
+pub fn apply_template(template: &ProjectTemplate, target: &Path) -> Result<Vec<String>, String> {
+    if let Some(invalid) = template.files.iter().find(|f| !is_relative_path(&f.path.replace('\\', "/"))) {
+        return Err(format!("模板中的文件路径无效: {}", invalid.path));
+    }
+
+    let mut written = Vec::new();
+    for template_file in &template.files {
+        let path = target.join(&template_file.path);
+        if path.exists() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        fs::write(&path, &template_file.content)
+            .map_err(|e| format!("无法写入模板文件 {}: {}", template_file.path, e))?;
+        written.push(template_file.path.clone());
+    }
+
+    Ok(written)
+}
+
+fn collect_files(dir: &Path, base: &Path, files: &mut Vec<TemplateFile>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    for entry in entries.flatten() {
+        if files.len() >= MAX_TEMPLATE_FILES {
+            return;
+        }
+
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            collect_files(&path, base, files);
+            continue;
+        }
+
+        if entry.metadata().map(|m| m.len() > MAX_TEMPLATE_FILE_SIZE).unwrap_or(true) {
+            continue;
+        }
+        // 只收录文本文件
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        let Ok(relative) = path.strip_prefix(base) else { continue };
+
+        files.push(TemplateFile {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            content,
+        });
+    }
+}
+
+/// 把需求文档中的项目名称和描述换成占位符：替换一级标题和「项目描述」章节的正文，其他内容不变
+fn requirement_placeholders(requirement: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut title_done = false;
+    let mut in_description = false;
+
+    for line in requirement.lines() {
+        if line.starts_with("## ") {
+            in_description = SectionKind::from_heading(&line[3..]) == SectionKind::Description;
+            out.push(line.to_string());
+            if in_description {
+                out.push("{{description}}".to_string());
+                out.push(String::new());
+            }
+        } else if in_description {
+            continue;
+        } else if !title_done && line.starts_with("# ") {
+            title_done = true;
+            out.push("# {{name}} 需求文档".to_string());
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    out.join("\n") + "\n"
+}
+
+/// 从项目代码目录创建模板并保存
+pub fn save_template(
+    templates_dir: &Path,
+    project: &Project,
+    source_root: &Path,
+    requirement: &str,
+    name: String,
+    description: String,
+) -> Result<ProjectTemplate, String> {
+    let mut files = Vec::new();
+    collect_files(source_root, source_root, &mut files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // 需求文档中的项目名称和描述替换为占位符，方便新项目复用
+    let requirement = requirement_placeholders(requirement);
+
+    let template = ProjectTemplate {
+        id: format!("user-{}", uuid::Uuid::new_v4()),
+        name,
+        description,
+        language: project.language.clone(),
+        run_command: project.run_command.clone(),
+        test_command: project.test_command.clone(),
+        requirement,
+        files,
+        builtin: false,
+    };
+
+    fs::create_dir_all(templates_dir)
+        .map_err(|e| format!("无法创建模板目录: {}", e))?;
+    let content = serde_json::to_string_pretty(&template)
+        .map_err(|e| format!("序列化模板失败: {}", e))?;
+    fs::write(templates_dir.join(format!("{}.json", template.id)), content)
+        .map_err(|e| format!("保存模板失败: {}", e))?;
+
+    Ok(template)
+}
+
+/// 删除用户模板，内置模板不能删除
+pub fn delete_template(templates_dir: &Path, template_id: &str) -> Result<(), String> {
+    if builtin_templates().iter().any(|t| t.id == template_id) {
+        return Err("内置模板不能删除".to_string());
+    }
+    if !is_valid_id(template_id) {
+        return Err(format!("无效的模板 id: {}", template_id));
+    }
+
+    let path = templates_dir.join(format!("{}.json", template_id));
+    if !path.exists() {
+        return Err(format!("模板不存在: {}", template_id));
+    }
+    fs::remove_file(&path).map_err(|e| format!("删除模板失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_are_unique() {
+        let templates = builtin_templates();
+        for template in &templates {
+            assert_eq!(templates.iter().filter(|t| t.id == template.id).count(), 1);
+            assert!(!template.files.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_render_requirement() {
+        let rendered = python_cli().render_requirement("计算器", "一个简单的计算器");
+        assert!(rendered.starts_with("# 计算器 需求文档"));
+        assert!(rendered.contains("## 项目描述\n一个简单的计算器"));
+        // 验收标准只有提示，不会解析出空的条目
+        let model = crate::requirement::RequirementDoc::parse(&rendered).model();
+        assert!(model.acceptance.is_empty());
+    }
+
+    #[test]
+    fn test_requirement_placeholders() {
+        let requirement = "# 计算器 需求文档\n\n## 项目描述\n计算器的简单实现\n\n## 功能需求\n1. 计算器支持加法\n";
+        let template = requirement_placeholders(requirement);
+        assert_eq!(
+            template,
+            "# {{name}} 需求文档\n\n## 项目描述\n{{description}}\n\n## 功能需求\n1. 计算器支持加法\n"
+        );
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_target() {
+        let dir = std::env::temp_dir().join(format!("code-sensei-templates-{}", std::process::id()));
+        let mut template = python_cli();
+        template.files = vec![file("../escape.py", "")];
+        let applied = apply_template(&template, &dir);
+        template.files = vec![file("/tmp/escape.py", "")];
+        let absolute = apply_template(&template, &dir);
+        let deleted = delete_template(&dir, "../../x");
+
+        assert!(applied.is_err() && absolute.is_err());
+        assert!(deleted.unwrap_err().contains("无效"));
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,570 @@
+// 单元测试生成与执行：确定测试框架和测试文件位置，运行测试并解析每个用例的结果
+use crate::runner::RunCommand;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 运行测试的超时时间（秒）
+const TEST_TIMEOUT_SECS: u64 = 300;
+
+/// 结果中保留的原始输出最大字节数
+const OUTPUT_LIMIT: usize = 32 * 1024;
+
+/// Rust 源文件中生成的测试模块的起始标记，重新生成时从这里替换
+const RUST_TEST_MARKER: &str = "// ===== Code Sensei 生成的测试 =====";
+
+/// 生成的 Rust 测试模块名，避免与源文件中已有的 `mod tests` 重名
+const RUST_TEST_MODULE: &str = "generated_tests";
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Pytest,
+    Cargo,
+    Jest,
+}
+
+impl TestFramework {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestFramework::Pytest => "pytest",
+            TestFramework::Cargo => "cargo test",
+            TestFramework::Jest => "jest",
+        }
+    }
+
+    /// 根据项目配置的测试命令判断测试框架，用于解析输出
+    pub fn from_command(command: &str) -> Option<Self> {
+        if command.contains("pytest") {
+            Some(TestFramework::Pytest)
+        } else if command.trim_start().starts_with("cargo") {
+            Some(TestFramework::Cargo)
+        } else if command.contains("jest") {
+            Some(TestFramework::Jest)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCaseResult {
+    pub name: String,
+    /// "passed" / "failed" / "skipped" / "error"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRunResult {
+    pub framework: TestFramework,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub cases: Vec<TestCaseResult>,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedTests {
+    pub framework: TestFramework,
+    pub test_file: String,
+    pub content: String,
+    pub result: TestRunResult,
+}
+
+// ===== 框架与文件位置 =====
+
+/// 根据源文件类型和项目清单确定测试框架
+pub fn detect_framework(root: &Path, relative_path: Option<&str>) -> Result<TestFramework, String> {
+    if let Some(path) = relative_path {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        return match ext.as_str() {
+            "py" => Ok(TestFramework::Pytest),
+            "rs" => Ok(TestFramework::Cargo),
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Ok(TestFramework::Jest),
+            _ => Err(format!("暂不支持为该类型的文件生成测试: {}", path)),
+        };
+    }
+
+    if root.join("Cargo.toml").exists() {
+        Ok(TestFramework::Cargo)
+    } else if root.join("package.json").exists() {
+        Ok(TestFramework::Jest)
+    } else {
+        Ok(TestFramework::Pytest)
+    }
+}
+
+/// 测试文件的惯用位置（相对于项目根目录）
+///
+/// - pytest: `tests/` 下与源文件相同的目录中的 `test_<name>.py`
+/// - jest: 与源文件同目录的 `<name>.test.<ext>`
+/// - cargo: 源文件内的 `#[cfg(test)]` 模块
+///
+/// 源文件必须是项目内的相对路径
+pub fn test_file_path(framework: TestFramework, relative_path: &str) -> Result<String, String> {
+    let path = Path::new(relative_path);
+    if relative_path.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("无效的文件路径: {}", relative_path));
+    }
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("module");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("js");
+    let parent = path
+        .parent()
+        .and_then(|p| p.to_str())
+        .unwrap_or("")
+        .replace('\\', "/");
+
+    // 不同目录下的同名文件使用不同的测试文件
+    let file = match framework {
+        TestFramework::Pytest if parent.is_empty() => format!("tests/test_{}.py", stem),
+        TestFramework::Pytest => format!("tests/{}/test_{}.py", parent, stem),
+        TestFramework::Jest if parent.is_empty() => format!("{}.test.{}", stem, ext),
+        TestFramework::Jest => format!("{}/{}.test.{}", parent, stem, ext),
+        TestFramework::Cargo => relative_path.replace('\\', "/"),
+    };
+    Ok(file)
+}
+
+/// 构建生成测试的提示词
+pub fn build_generate_prompt(
+    framework: TestFramework,
+    relative_path: &str,
+    source: &str,
+    test_file: &str,
+) -> String {
+    let placement = match framework {
+        TestFramework::Pytest => {
+            let module = relative_path.trim_end_matches(".py").replace('/', ".");
+            format!(
+                "测试文件将保存为 `{}`，在项目根目录下通过 `python -m pytest` 运行。\n请使用 `import {}` 或 `from {} import ...` 导入被测代码。",
+                test_file, module, module
+            )
+        }
+        TestFramework::Jest => {
+            let stem = Path::new(relative_path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("module");
+            format!(
+                "测试文件将保存为 `{}`（与源文件同目录），通过 `npx jest` 运行。\n请通过相对路径 `./{}` 导入被测代码，导入方式（require 或 import）与源文件保持一致。",
+                test_file, stem
+            )
+        }
+        TestFramework::Cargo => {
+            format!(
+                "测试将作为 `#[cfg(test)] mod {}` 模块追加到源文件末尾，通过 `cargo test` 运行。\n只输出这个测试模块本身（以 `#[cfg(test)]` 开头，模块内使用 `use super::*;`），不要重复源文件中的代码。",
+                RUST_TEST_MODULE
+            )
+        }
+    };
+
+    format!(
+        "你是 Code Sensei 的编程老师，正在为初学者的代码编写单元测试，帮助他们学习如何测试。
+
+## 被测文件
+{}
+
+```
+{}
+```
+
+## 测试框架
+{}
+
+## 测试位置
+{}
+
+## 要求
+- 覆盖主要功能、边界情况和错误处理
+- 每个测试函数命名清晰，并用简短的注释说明测试的目的
+- 不要使用工具创建或修改任何文件
+- 只在一个代码块中输出完整的测试代码，不要有其他说明",
+        relative_path,
+        source,
+        framework.name(),
+        placement
+    )
+}
+
+/// 提取回复中第一个代码块的内容，没有代码块时返回整个回复
+pub fn extract_code_block(text: &str) -> String {
+    let Some(start) = text.find("```") else {
+        return text.trim().to_string();
+    };
+    let after_fence = &text[start + 3..];
+    // 跳过语言标记所在的行
+    let body = match after_fence.find('\n') {
+        Some(pos) => &after_fence[pos + 1..],
+        None => after_fence,
+    };
+    match body.find("```") {
+        Some(end) => body[..end].trim_end().to_string(),
+        None => body.trim_end().to_string(),
+    }
+}
+
+/// 将生成的测试模块合并到 Rust 源文件，替换之前生成的测试；
+/// AI 仍然输出 `mod tests` 时改为 `RUST_TEST_MODULE`，避免与源文件中已有的测试模块冲突
+pub fn merge_rust_tests(source: &str, tests: &str) -> String {
+    let code = match source.find(RUST_TEST_MARKER) {
+        Some(pos) => &source[..pos],
+        None => source,
+    };
+    let module = Regex::new(r"\bmod\s+tests\b").unwrap();
+    let tests = module.replace(tests.trim(), format!("mod {}", RUST_TEST_MODULE));
+    format!("{}\n\n{}\n{}\n", code.trim_end(), RUST_TEST_MARKER, tests)
+}
+
+// ===== 运行与解析 =====
+
+fn test_command(framework: TestFramework, test_file: Option<&str>) -> (String, Vec<String>) {
+    match framework {
+        TestFramework::Pytest => {
+            let program = if cfg!(windows) { "python" } else { "python3" };
+            // importlib 模式允许不同目录中有同名的测试文件
+            let mut args = vec![
+                "-m",
+                "pytest",
+                "-v",
+                "-rA",
+                "--tb=short",
+                "-p",
+                "no:cacheprovider",
+                "--import-mode=importlib",
+            ];
+            args.extend(test_file);
+            (program.to_string(), args.into_iter().map(String::from).collect())
+        }
+        TestFramework::Cargo => (
+            "cargo".to_string(),
+            vec!["test".to_string(), "--".to_string(), "--test-threads=1".to_string()],
+        ),
+        TestFramework::Jest => {
+            let program = if cfg!(windows) { "npx.cmd" } else { "npx" };
+            let mut args = vec!["--yes", "jest", "--json"];
+            args.extend(test_file);
+            (program.to_string(), args.into_iter().map(String::from).collect())
+        }
+    }
+}
+
+fn case(name: &str, status: &str, message: Option<String>, duration_ms: Option<u64>) -> TestCaseResult {
+    TestCaseResult {
+        name: name.to_string(),
+        status: status.to_string(),
+        message,
+        duration_ms,
+    }
+}
+
+/// 解析 `pytest -v -rA` 的输出
+pub fn parse_pytest_output(output: &str) -> Vec<TestCaseResult> {
+    let mut cases: Vec<TestCaseResult> = Vec::new();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        // 详细模式: tests/test_x.py::test_add PASSED [ 50%]
+        if let Some((name, rest)) = trimmed.split_once(' ') {
+            if name.contains("::") {
+                let status = match rest.split_whitespace().next().unwrap_or("") {
+                    "PASSED" | "XPASS" => "passed",
+                    "FAILED" => "failed",
+                    "ERROR" => "error",
+                    "SKIPPED" | "XFAIL" => "skipped",
+                    _ => continue,
+                };
+                if !cases.iter().any(|c| c.name == name) {
+                    cases.push(case(name, status, None, None));
+                }
+                continue;
+            }
+        }
+
+        // 摘要: FAILED tests/test_x.py::test_b - assert 1 == 2
+        for (prefix, status) in [("FAILED ", "failed"), ("ERROR ", "error")] {
+            let Some(rest) = trimmed.strip_prefix(prefix) else { continue };
+            let (name, message) = match rest.split_once(" - ") {
+                Some((name, message)) => (name.trim(), Some(message.trim().to_string())),
+                None => (rest.trim(), None),
+            };
+            match cases.iter_mut().find(|c| c.name == name) {
+                Some(existing) => existing.message = message,
+                None => cases.push(case(name, status, message, None)),
+            }
+        }
+    }
+
+    cases
+}
+
+/// 解析 `cargo test` 的输出
+pub fn parse_cargo_test_output(output: &str) -> Vec<TestCaseResult> {
+    let mut cases: Vec<TestCaseResult> = Vec::new();
+
+    for line in output.lines() {
+        // test tests::it_works ... ok
+        let Some(rest) = line.strip_prefix("test ") else { continue };
+        let Some((name, status)) = rest.rsplit_once(" ... ") else { continue };
+        let status = match status.trim() {
+            "ok" => "passed",
+            "FAILED" => "failed",
+            s if s.starts_with("ignored") => "skipped",
+            _ => continue,
+        };
+        cases.push(case(name.trim(), status, None, None));
+    }
+
+    // 失败详情: ---- tests::name stdout ---- 之后直到下一个空行分隔的段落
+    let mut current: Option<String> = None;
+    let mut details: Vec<String> = Vec::new();
+    let flush = |name: &Option<String>, details: &mut Vec<String>, cases: &mut Vec<TestCaseResult>| {
+        if let Some(name) = name {
+            if let Some(c) = cases.iter_mut().find(|c| &c.name == name) {
+                let message = details.join("\n").trim().to_string();
+                if !message.is_empty() {
+                    c.message = Some(message);
+                }
+            }
+        }
+        details.clear();
+    };
+
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            flush(&current, &mut details, &mut cases);
+            current = Some(name.to_string());
+        } else if current.is_some() {
+            if line.starts_with("failures:") || line.starts_with("test result:") {
+                flush(&current, &mut details, &mut cases);
+                current = None;
+            } else {
+                details.push(line.to_string());
+            }
+        }
+    }
+    flush(&current, &mut details, &mut cases);
+
+    cases
+}
+
+/// 解析 `jest --json` 的输出
+pub fn parse_jest_output(output: &str) -> Vec<TestCaseResult> {
+    // --json 的结果是单独一行 JSON，前后可能夹杂其他日志
+    let report = output
+        .lines()
+        .filter(|l| l.trim_start().starts_with('{'))
+        .find_map(|l| serde_json::from_str::<serde_json::Value>(l.trim()).ok())
+        .or_else(|| {
+            let start = output.find('{')?;
+            let end = output.rfind('}')?;
+            serde_json::from_str::<serde_json::Value>(&output[start..=end]).ok()
+        });
+
+    let Some(report) = report else { return Vec::new() };
+    let mut cases = Vec::new();
+
+    for suite in report["testResults"].as_array().into_iter().flatten() {
+        for assertion in suite["assertionResults"].as_array().into_iter().flatten() {
+            let name = assertion["fullName"]
+                .as_str()
+                .or_else(|| assertion["title"].as_str())
+                .unwrap_or("");
+            let status = match assertion["status"].as_str().unwrap_or("") {
+                "passed" => "passed",
+                "failed" => "failed",
+                _ => "skipped",
+            };
+            let message = assertion["failureMessages"]
+                .as_array()
+                .map(|m| m.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>().join("\n"))
+                .filter(|m| !m.is_empty());
+            let duration = assertion["duration"].as_u64();
+            cases.push(case(name, status, message, duration));
+        }
+
+        // 测试文件本身加载失败（语法错误等）时没有用例结果
+        if suite["status"] == "failed" && suite["assertionResults"].as_array().map(|a| a.is_empty()).unwrap_or(true) {
+            let name = suite["name"].as_str().unwrap_or("test suite");
+            let message = suite["message"].as_str().map(|m| m.to_string());
+            cases.push(case(name, "error", message, None));
+        }
+    }
+
+    cases
+}
+
+/// 保留输出末尾
+fn truncate_output(output: String) -> String {
+    if output.len() <= OUTPUT_LIMIT {
+        return output;
+    }
+    let mut cut = output.len() - OUTPUT_LIMIT;
+    while !output.is_char_boundary(cut) {
+        cut += 1;
+    }
+    output[cut..].to_string()
+}
+
+/// 在项目根目录运行测试并解析结果
+pub async fn run_tests(
+    root: &Path,
+    framework: TestFramework,
+    test_file: Option<&str>,
+    custom_command: Option<&str>,
+) -> Result<TestRunResult, String> {
+    // Rust 测试位于源文件内，运行整个 crate 的测试
+    let file_arg = match framework {
+        TestFramework::Cargo => None,
+        _ => test_file,
+    };
+    // 项目配置了测试命令时直接使用，否则使用测试框架的默认命令
+    let (program, args) = match custom_command.and_then(RunCommand::parse) {
+        Some(command) => (command.program, command.args),
+        None => test_command(framework, file_arg),
+    };
+    let command_display = std::iter::once(program.clone())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    println!("🧪 运行测试: {} (目录: {})", command_display, root.display());
+
+    let child = Command::new(&program)
+        .args(&args)
+        .current_dir(root)
+        .env("PYTHONUNBUFFERED", "1")
+        .env("CI", "true")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("无法运行测试命令 `{}`: {}", command_display, e))?;
+
+    let (output, timed_out) = match tokio::time::timeout(
+        Duration::from_secs(TEST_TIMEOUT_SECS),
+        child.wait_with_output(),
+    )
+    .await
+    {
+        Ok(output) => (Some(output.map_err(|e| format!("读取测试输出失败: {}", e))?), false),
+        Err(_) => (None, true),
+    };
+
+    let (exit_code, stdout, stderr) = match output {
+        Some(output) => (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ),
+        None => (None, String::new(), String::new()),
+    };
+
+    let cases = match framework {
+        TestFramework::Pytest => parse_pytest_output(&stdout),
+        TestFramework::Cargo => parse_cargo_test_output(&stdout),
+        TestFramework::Jest => parse_jest_output(&stdout),
+    };
+
+    let count = |status: &str| cases.iter().filter(|c| c.status == status).count();
+    let passed = count("passed");
+    let failed = count("failed") + count("error");
+    let skipped = count("skipped");
+
+    Ok(TestRunResult {
+        framework,
+        command: command_display,
+        test_file: test_file.map(|f| f.to_string()),
+        exit_code,
+        timed_out,
+        passed,
+        failed,
+        skipped,
+        cases,
+        output: truncate_output(format!("{}\n{}", stdout, stderr)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pytest_output() {
+        let output = "tests/test_calc.py::test_add PASSED                 [ 50%]
+tests/test_calc.py::test_div FAILED                 [100%]
+=========================== short test summary info ============================
+PASSED tests/test_calc.py::test_add
+FAILED tests/test_calc.py::test_div - ZeroDivisionError: division by zero";
+
+        let cases = parse_pytest_output(output);
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].status, "passed");
+        assert_eq!(cases[1].status, "failed");
+        assert_eq!(cases[1].message.as_deref(), Some("ZeroDivisionError: division by zero"));
+    }
+
+    #[test]
+    fn test_parse_cargo_test_output() {
+        let output = "running 2 tests
+test tests::it_adds ... ok
+test tests::it_fails ... FAILED
+
+failures:
+
+---- tests::it_fails stdout ----
+assertion `left == right` failed
+
+failures:
+    tests::it_fails
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored";
+
+        let cases = parse_cargo_test_output(output);
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[1].name, "tests::it_fails");
+        assert_eq!(cases[1].message.as_deref(), Some("assertion `left == right` failed"));
+    }
+
+    #[test]
+    fn test_test_file_path_and_merge() {
+        assert_eq!(test_file_path(TestFramework::Pytest, "calc.py").unwrap(), "tests/test_calc.py");
+        assert_eq!(test_file_path(TestFramework::Pytest, "a/utils.py").unwrap(), "tests/a/test_utils.py");
+        assert_eq!(test_file_path(TestFramework::Jest, "lib/sum.js").unwrap(), "lib/sum.test.js");
+        assert!(test_file_path(TestFramework::Cargo, "../x.rs").is_err());
+        assert!(test_file_path(TestFramework::Cargo, "/tmp/x.rs").is_err());
+
+        let merged = merge_rust_tests("fn a() {}\n", "#[cfg(test)]\nmod tests {}");
+        let regenerated = merge_rust_tests(&merged, "#[cfg(test)]\nmod tests { }");
+        assert_eq!(regenerated.matches(RUST_TEST_MARKER).count(), 1);
+        assert!(regenerated.starts_with("fn a() {}"));
+
+        let existing = merge_rust_tests("fn a() {}\n\n#[cfg(test)]\nmod tests {}\n", "#[cfg(test)]\nmod tests { }");
+        assert_eq!(existing.matches("mod tests").count(), 1);
+        assert!(existing.contains("mod generated_tests { }"));
+    }
+}
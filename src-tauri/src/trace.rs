@@ -0,0 +1,400 @@
+// 需求追踪：记录需求条目与实现它的任务和源文件之间的关联，并生成覆盖情况报告
+use crate::history;
+use crate::ignore;
+use crate::language;
+use crate::requirement::{RequirementDoc, RequirementItem};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// 关联记录保存的文件名（位于项目目录）
+const TRACE_FILE: &str = "trace.json";
+
+/// 记录修改时间时最多扫描的文件数
+const MAX_SNAPSHOT_FILES: usize = 5000;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    File,
+    Task,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkSource {
+    /// 用户手动添加
+    Manual,
+    /// 根据 Agent 修改的文件自动添加
+    Agent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceLink {
+    pub requirement_id: String,
+    pub kind: LinkKind,
+    /// 文件的相对路径或任务 id
+    pub target: String,
+    pub source: LinkSource,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraceStore {
+    pub links: Vec<TraceLink>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStatus {
+    /// 有实现文件，且需求在代码最后修改之后没有变化
+    Covered,
+    /// 没有实现文件
+    Uncovered,
+    /// 需求在实现文件最后修改之后发生了变化
+    Stale,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementCoverage {
+    pub id: String,
+    pub text: String,
+    pub files: Vec<String>,
+    pub tasks: Vec<String>,
+    /// 需求内容最后一次变化的时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement_changed_at: Option<i64>,
+    /// 实现文件最后一次修改的时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_touched_at: Option<i64>,
+    pub status: CoverageStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub requirements: Vec<RequirementCoverage>,
+    pub uncovered: Vec<String>,
+    pub stale: Vec<String>,
+    /// 没有关联任何需求的代码文件
+    pub untraced_files: Vec<String>,
+    /// 指向已删除的需求或文件的关联
+    pub broken_links: Vec<TraceLink>,
+}
+
+// ===== 关联记录 =====
+
+pub fn load_store(project_dir: &Path) -> TraceStore {
+    fs::read_to_string(project_dir.join(TRACE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_store(project_dir: &Path, store: &TraceStore) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(store).map_err(|e| format!("无法序列化需求追踪记录: {}", e))?;
+    fs::write(project_dir.join(TRACE_FILE), content).map_err(|e| format!("无法保存需求追踪记录: {}", e))
+}
+
+impl TraceStore {
+    /// 添加关联，已存在的关联不重复添加，返回新增的数量
+    pub fn add(&mut self, requirement_id: &str, kind: LinkKind, targets: &[String], source: LinkSource) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        let mut added = 0;
+        for target in targets {
+            let target = target.trim().replace('\\', "/");
+            if target.is_empty() {
+                continue;
+            }
+            let exists = self
+                .links
+                .iter()
+                .any(|l| l.requirement_id == requirement_id && l.kind == kind && l.target == target);
+            if !exists {
+                self.links.push(TraceLink {
+                    requirement_id: requirement_id.to_string(),
+                    kind,
+                    target,
+                    source,
+                    created_at: now,
+                });
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// 删除关联，返回是否删除了记录
+    pub fn remove(&mut self, requirement_id: &str, kind: LinkKind, target: &str) -> bool {
+        let before = self.links.len();
+        self.links
+            .retain(|l| !(l.requirement_id == requirement_id && l.kind == kind && l.target == target));
+        self.links.len() != before
+    }
+}
+
+// ===== Agent 修改记录 =====
+
+/// 记录目录中所有文件的修改时间，用于找出 Agent 修改过的文件
+pub fn snapshot_mtimes(root: &Path) -> HashMap<String, SystemTime> {
+    let mut files = HashMap::new();
+    collect_mtimes(root, root, &mut files);
+    files
+}
+
+fn collect_mtimes(dir: &Path, base: &Path, files: &mut HashMap<String, SystemTime>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        if files.len() >= MAX_SNAPSHOT_FILES {
+            return;
+        }
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            collect_mtimes(&path, base, files);
+        } else if let (Ok(relative), Ok(modified)) = (path.strip_prefix(base), entry.metadata().and_then(|m| m.modified())) {
+            files.insert(relative.to_string_lossy().replace('\\', "/"), modified);
+        }
+    }
+}
+
+/// 两次快照之间新增或修改的文件
+pub fn changed_files(before: &HashMap<String, SystemTime>, after: &HashMap<String, SystemTime>) -> Vec<String> {
+    let mut changed: Vec<String> = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path).map(|old| old != *modified).unwrap_or(true))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// 文本中提到的需求 id，「FR-1」不会匹配到「FR-10」
+pub fn mentioned_ids(text: &str, ids: &[String]) -> Vec<String> {
+    ids.iter()
+        .filter(|id| {
+            text.match_indices(id.as_str()).any(|(start, _)| {
+                let before = text[..start].chars().next_back();
+                let after = text[start + id.len()..].chars().next();
+                !before.map(|c| c.is_ascii_alphanumeric()).unwrap_or(false)
+                    && !after.map(|c| c.is_ascii_digit()).unwrap_or(false)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// 把 Agent 修改的文件关联到用户输入和 Agent 回复中提到的需求，返回新增的关联数
+pub fn link_agent_edits(project_dir: &Path, requirement: &str, text: &str, files: &[String]) -> Result<usize, String> {
+    if files.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<String> = traceable_items(&RequirementDoc::parse(requirement))
+        .into_iter()
+        .map(|item| item.id)
+        .collect();
+    let mentioned = mentioned_ids(text, &ids);
+    if mentioned.is_empty() {
+        return Ok(0);
+    }
+
+    let mut store = load_store(project_dir);
+    let added: usize = mentioned
+        .iter()
+        .map(|id| store.add(id, LinkKind::File, files, LinkSource::Agent))
+        .sum();
+    if added > 0 {
+        save_store(project_dir, &store)?;
+    }
+    Ok(added)
+}
+
+/// 需要追踪实现情况的需求条目：功能需求、非功能需求和验收标准
+pub fn traceable_items(doc: &RequirementDoc) -> Vec<RequirementItem> {
+    let model = doc.model();
+    model
+        .functional
+        .into_iter()
+        .chain(model.non_functional)
+        .chain(model.acceptance)
+        .collect()
+}
+
+// ===== 覆盖报告 =====
+
+fn to_secs(time: SystemTime) -> i64 {
+    chrono::DateTime::<chrono::Utc>::from(time).timestamp()
+}
+
+/// 根据版本历史计算每条需求最后一次变化的时间
+///
+/// 当前文档与最新版本不同（例如在应用外修改）时，以文档的修改时间为准。
+fn requirement_change_times(project_dir: &Path, requirement_file: &Path, current: &[RequirementItem]) -> HashMap<String, i64> {
+    let mut last_text: HashMap<String, String> = HashMap::new();
+    let mut changed_at: HashMap<String, i64> = HashMap::new();
+
+    let mut record = |items: Vec<RequirementItem>, time: i64| {
+        for item in items {
+            if last_text.get(&item.id) != Some(&item.text) {
+                changed_at.insert(item.id.clone(), time);
+                last_text.insert(item.id, item.text);
+            }
+        }
+    };
+
+    for revision in history::revisions(project_dir) {
+        record(traceable_items(&RequirementDoc::parse(&revision.content)), revision.created_at / 1000);
+    }
+
+    let file_time = fs::metadata(requirement_file)
+        .and_then(|m| m.modified())
+        .map(to_secs)
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp());
+    record(current.to_vec(), file_time);
+
+    changed_at
+}
+
+/// 收集目录下的源代码文件（相对路径），跳过忽略规则中的目录和文件
+pub fn collect_code_files(dir: &Path, base: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if ignore::is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            collect_code_files(&path, base, files);
+            continue;
+        }
+        let is_code = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(language::language_for_extension)
+            .is_some();
+        if is_code {
+            if let Ok(relative) = path.strip_prefix(base) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+}
+
+/// 生成需求覆盖报告
+pub fn coverage_report(project_dir: &Path, source_root: &Path, requirement_file: &Path) -> CoverageReport {
+    let content = fs::read_to_string(requirement_file).unwrap_or_default();
+    let items = traceable_items(&RequirementDoc::parse(&content));
+    let store = load_store(project_dir);
+    let changed_at = requirement_change_times(project_dir, requirement_file, &items);
+
+    let ids: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+    let broken_links: Vec<TraceLink> = store
+        .links
+        .iter()
+        .filter(|l| !ids.contains(l.requirement_id.as_str()) || (l.kind == LinkKind::File && !source_root.join(&l.target).is_file()))
+        .cloned()
+        .collect();
+
+    let mut requirements = Vec::new();
+    for item in &items {
+        let links: Vec<&TraceLink> = store.links.iter().filter(|l| l.requirement_id == item.id).collect();
+        let files: Vec<String> = links
+            .iter()
+            .filter(|l| l.kind == LinkKind::File && source_root.join(&l.target).is_file())
+            .map(|l| l.target.clone())
+            .collect();
+        let tasks: Vec<String> = links.iter().filter(|l| l.kind == LinkKind::Task).map(|l| l.target.clone()).collect();
+
+        let code_touched_at = files
+            .iter()
+            .filter_map(|f| fs::metadata(source_root.join(f)).and_then(|m| m.modified()).ok())
+            .map(to_secs)
+            .max();
+        let requirement_changed_at = changed_at.get(&item.id).copied();
+
+        let status = match (code_touched_at, requirement_changed_at) {
+            (None, _) => CoverageStatus::Uncovered,
+            (Some(code), Some(requirement)) if requirement > code => CoverageStatus::Stale,
+            _ => CoverageStatus::Covered,
+        };
+
+        requirements.push(RequirementCoverage {
+            id: item.id.clone(),
+            text: item.text.clone(),
+            files,
+            tasks,
+            requirement_changed_at,
+            code_touched_at,
+            status,
+        });
+    }
+
+    let traced: HashSet<&str> = store
+        .links
+        .iter()
+        .filter(|l| l.kind == LinkKind::File && ids.contains(l.requirement_id.as_str()))
+        .map(|l| l.target.as_str())
+        .collect();
+    let mut code_files = Vec::new();
+    collect_code_files(source_root, source_root, &mut code_files);
+    let mut untraced_files: Vec<String> = code_files.into_iter().filter(|f| !traced.contains(f.as_str())).collect();
+    untraced_files.sort();
+
+    let with_status = |status: CoverageStatus| {
+        requirements
+            .iter()
+            .filter(|r| r.status == status)
+            .map(|r| r.id.clone())
+            .collect::<Vec<_>>()
+    };
+
+    CoverageReport {
+        uncovered: with_status(CoverageStatus::Uncovered),
+        stale: with_status(CoverageStatus::Stale),
+        requirements,
+        untraced_files,
+        broken_links,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentioned_ids() {
+        let ids = vec!["FR-1".to_string(), "FR-10".to_string(), "NFR-1".to_string()];
+        assert_eq!(mentioned_ids("实现了 FR-10 和 NFR-1", &ids), vec!["FR-10", "NFR-1"]);
+        assert_eq!(mentioned_ids("完成 **FR-1**。", &ids), vec!["FR-1"]);
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let base = std::env::temp_dir().join(format!("code-sensei-trace-{}", std::process::id()));
+        let src = base.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(base.join("requirement.md"), "# Demo\n\n## 功能需求\n- 登录\n- 注册\n").unwrap();
+        fs::write(src.join("login.py"), "").unwrap();
+        fs::write(src.join("util.py"), "").unwrap();
+
+        let mut store = TraceStore::default();
+        store.add("FR-1", LinkKind::File, &["login.py".to_string()], LinkSource::Manual);
+        store.add("FR-9", LinkKind::Task, &["task-1".to_string()], LinkSource::Manual);
+        save_store(&base, &store).unwrap();
+
+        let report = coverage_report(&base, &src, &base.join("requirement.md"));
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(report.requirements[0].files, vec!["login.py"]);
+        assert_eq!(report.uncovered, vec!["FR-2"]);
+        assert_eq!(report.untraced_files, vec!["util.py"]);
+        assert_eq!(report.broken_links.len(), 1);
+    }
+}
@@ -0,0 +1,338 @@
+// 文件监听：监听打开的项目目录，合并短时间内的修改后把新增、修改、删除和重命名推送给前端
+use crate::ignore;
+use crate::replace;
+use crate::search;
+use notify_debouncer_full::notify::event::{ModifyKind, RemoveKind, RenameMode};
+use notify_debouncer_full::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 合并修改的时间窗口
+const DEBOUNCE_MS: u64 = 500;
+
+// ===== 数据结构 =====
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    /// 相对于项目根目录的路径，重命名时为新路径
+    pub path: String,
+    /// 重命名前的路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub is_dir: bool,
+}
+
+/// 一次推送的修改，通过 `project-files-changed` 事件发送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChanges {
+    pub project_id: String,
+    pub changes: Vec<FileChange>,
+}
+
+// ===== 事件转换 =====
+
+/// 项目内的相对路径；被文件树忽略的路径返回 None
+fn visible_path(root: &Path, path: &Path, is_dir: bool) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<&str> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    let (name, dirs) = parts.split_last()?;
+    if dirs.iter().any(|d| ignore::is_hidden(d) || ignore::is_skipped_dir(d)) {
+        return None;
+    }
+    if name.ends_with(replace::TEMP_SUFFIX) || ignore::is_ignored(Path::new(name), is_dir) {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+fn change(kind: ChangeKind, path: String, is_dir: bool) -> FileChange {
+    FileChange { kind, path, from: None, is_dir }
+}
+
+/// 项目中已有的文件（相对路径），用来区分移入的文件是新增还是覆盖了已有文件
+pub fn existing_files(root: &Path) -> HashSet<String> {
+    let mut files = HashSet::new();
+    let _ = search::walk_files(root, root, &mut |relative, _| {
+        files.insert(relative);
+        ControlFlow::Continue(())
+    });
+    files
+}
+
+/// 按推送的修改更新已有文件的集合
+pub fn record(files: &mut HashSet<String>, changes: &[FileChange]) {
+    for c in changes {
+        let moved_from = match c.kind {
+            ChangeKind::Deleted => Some(&c.path),
+            ChangeKind::Renamed => c.from.as_ref(),
+            _ => None,
+        };
+        if let Some(from) = moved_from {
+            let prefix = format!("{}/", from);
+            let inner: Vec<String> = files.iter().filter(|f| f.starts_with(&prefix)).cloned().collect();
+            files.remove(from);
+            for f in inner {
+                files.remove(&f);
+                if c.kind == ChangeKind::Renamed {
+                    files.insert(format!("{}/{}", c.path, &f[prefix.len()..]));
+                }
+            }
+        }
+        if c.kind != ChangeKind::Deleted && !c.is_dir {
+            files.insert(c.path.clone());
+        }
+    }
+}
+
+/// 把一个文件系统事件转换为前端需要的修改，忽略的路径和访问事件会被丢掉。
+/// `existing` 为事件发生前项目中已有的文件，移到已有文件上（例如先写临时文件再重命名的保存方式）视为修改
+pub fn classify(root: &Path, event: &Event, existing: &HashSet<String>) -> Vec<FileChange> {
+    let visible = |path: &PathBuf, is_dir: bool| visible_path(root, path, is_dir);
+    // 移入的文件覆盖了已有文件时视为修改
+    let moved_in = |path: String, is_dir: bool| {
+        let kind = if !is_dir && existing.contains(&path) {
+            ChangeKind::Modified
+        } else {
+            ChangeKind::Created
+        };
+        change(kind, path, is_dir)
+    };
+    // 路径已经不存在，事件没有说明是否为目录时，按已有文件中是否有它下面的文件判断
+    let removed = |path: &PathBuf, is_folder: bool| {
+        let relative = visible(path, false)?;
+        let prefix = format!("{}/", relative);
+        let is_dir = is_folder || existing.iter().any(|p| p.starts_with(&prefix));
+        let relative = if is_dir { visible(path, true)? } else { relative };
+        Some(change(ChangeKind::Deleted, relative, is_dir))
+    };
+
+    match event.kind {
+        EventKind::Create(_) => event
+            .paths
+            .iter()
+            .filter_map(|p| {
+                let is_dir = p.is_dir();
+                visible(p, is_dir).map(|path| change(ChangeKind::Created, path, is_dir))
+            })
+            .collect(),
+        EventKind::Remove(kind) => event
+            .paths
+            .iter()
+            .filter_map(|p| removed(p, kind == RemoveKind::Folder))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let is_dir = event.paths[1].is_dir();
+            // 从忽略的位置移入视为新增或修改，移到忽略的位置视为删除
+            match (visible(&event.paths[0], is_dir), visible(&event.paths[1], is_dir)) {
+                (Some(from), Some(path)) if !is_dir && existing.contains(&path) => vec![
+                    change(ChangeKind::Deleted, from, false),
+                    change(ChangeKind::Modified, path, false),
+                ],
+                (Some(from), Some(path)) => vec![FileChange {
+                    kind: ChangeKind::Renamed,
+                    path,
+                    from: Some(from),
+                    is_dir,
+                }],
+                (None, Some(path)) => vec![moved_in(path, is_dir)],
+                (Some(from), None) => vec![change(ChangeKind::Deleted, from, is_dir)],
+                (None, None) => Vec::new(),
+            }
+        }
+        // 只知道重命名的一端时，按路径是否存在判断是移入还是删除
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .filter_map(|p| {
+                if p.exists() {
+                    let is_dir = p.is_dir();
+                    visible(p, is_dir).map(|path| moved_in(path, is_dir))
+                } else {
+                    removed(p, false)
+                }
+            })
+            .collect(),
+        // 目录的元数据变化不影响文件树
+        EventKind::Modify(_) => event
+            .paths
+            .iter()
+            .filter(|p| !p.is_dir())
+            .filter_map(|p| visible(p, false).map(|path| change(ChangeKind::Modified, path, false)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 合并同一批次中的修改：去掉重复项，新增后紧接着的修改只保留新增
+pub fn coalesce(changes: Vec<FileChange>) -> Vec<FileChange> {
+    let created: Vec<String> = changes
+        .iter()
+        .filter(|c| c.kind == ChangeKind::Created)
+        .map(|c| c.path.clone())
+        .collect();
+    let mut out: Vec<FileChange> = Vec::new();
+    for c in changes {
+        if c.kind == ChangeKind::Modified && created.contains(&c.path) {
+            continue;
+        }
+        if !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// ===== 监听器 =====
+
+type ProjectDebouncer = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+/// 每个打开的项目一个监听器
+#[derive(Clone, Default)]
+pub struct WatcherRegistry {
+    watchers: Arc<Mutex<HashMap<String, ProjectDebouncer>>>,
+}
+
+impl WatcherRegistry {
+    /// 开始监听项目根目录，已在监听时替换为新的根目录
+    pub fn watch(&self, app: AppHandle, project_id: String, root: PathBuf) -> Result<(), String> {
+        let event_root = root.clone();
+        let event_project_id = project_id.clone();
+        let mut existing = existing_files(&root);
+        let mut debouncer = new_debouncer(Duration::from_millis(DEBOUNCE_MS), None, move |result: DebounceEventResult| {
+            match result {
+                Ok(events) => {
+                    let mut changes = Vec::new();
+                    for e in events.iter() {
+                        let classified = classify(&event_root, e, &existing);
+                        record(&mut existing, &classified);
+                        changes.extend(classified);
+                    }
+                    let changes = coalesce(changes);
+                    if !changes.is_empty() {
+                        let _ = app.emit("project-files-changed", FileChanges {
+                            project_id: event_project_id.clone(),
+                            changes,
+                        });
+                    }
+                }
+                Err(errors) => {
+                    for e in errors {
+                        eprintln!("文件监听出错: {}", e);
+                    }
+                }
+            }
+        })
+        .map_err(|e| format!("无法创建文件监听: {}", e))?;
+        debouncer
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| format!("无法监听目录 {}: {}", root.display(), e))?;
+
+        self.watchers.lock().unwrap().insert(project_id, debouncer);
+        Ok(())
+    }
+
+    /// 停止监听，返回之前是否在监听
+    pub fn unwatch(&self, project_id: &str) -> bool {
+        self.watchers.lock().unwrap().remove(project_id).is_some()
+    }
+
+    pub fn is_watching(&self, project_id: &str) -> bool {
+        self.watchers.lock().unwrap().contains_key(project_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange};
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |e, p| e.add_path(p.to_path_buf()))
+    }
+
+    #[test]
+    fn test_classify_applies_ignore_rules() {
+        let root = Path::new("/project");
+        let create = EventKind::Create(CreateKind::File);
+        let existing = HashSet::new();
+
+        let kept = classify(root, &event(create, &[&root.join("src/main.py")]), &existing);
+        let hidden = classify(root, &event(create, &[&root.join(".git/HEAD")]), &existing);
+        let skipped = classify(root, &event(create, &[&root.join("node_modules/a/index.js")]), &existing);
+        let binary = classify(root, &event(create, &[&root.join("lib.so")]), &existing);
+        let temp = classify(root, &event(create, &[&root.join("a.py.code-sensei-replace")]), &existing);
+
+        assert_eq!(kept, vec![change(ChangeKind::Created, "src/main.py".to_string(), false)]);
+        assert!(hidden.is_empty() && skipped.is_empty() && binary.is_empty() && temp.is_empty());
+    }
+
+    #[test]
+    fn test_classify_renames_and_coalesces() {
+        let root = Path::new("/project");
+        let mut existing: HashSet<String> = ["a.py", "b.py", "old.py", "pkg/mod.py"].iter().map(|s| s.to_string()).collect();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let renamed = classify(root, &event(rename, &[&root.join("old.py"), &root.join("new.py")]), &existing);
+        let restored = classify(root, &event(rename, &[&root.join("a.py.code-sensei-replace"), &root.join("a.py")]), &existing);
+        let moved_in = classify(root, &event(rename, &[&root.join(".cache/c.py"), &root.join("c.py")]), &existing);
+        let safe_write = classify(root, &event(rename, &[&root.join("b.py.tmp"), &root.join("b.py")]), &existing);
+        let removed = classify(root, &event(EventKind::Remove(RemoveKind::Folder), &[&root.join("pkg")]), &existing);
+        // 部分平台只报告 RemoveKind::Any
+        let removed_any = classify(root, &event(EventKind::Remove(RemoveKind::Any), &[&root.join("pkg")]), &existing);
+        let removed_file = classify(root, &event(EventKind::Remove(RemoveKind::Any), &[&root.join("a.py")]), &existing);
+        record(&mut existing, &renamed);
+        record(&mut existing, &removed);
+
+        let modify = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        let mut batch = classify(root, &event(EventKind::Create(CreateKind::File), &[&root.join("x.py")]), &existing);
+        batch.extend(classify(root, &event(modify, &[&root.join("x.py")]), &existing));
+        batch.extend(classify(root, &event(modify, &[&root.join("y.py")]), &existing));
+        batch.extend(classify(root, &event(modify, &[&root.join("y.py")]), &existing));
+
+        assert_eq!(renamed[0].kind, ChangeKind::Renamed);
+        assert_eq!(renamed[0].from.as_deref(), Some("old.py"));
+        assert_eq!(renamed[0].path, "new.py");
+        // 替换写入和编辑器的安全保存都是覆盖已有文件
+        assert_eq!(restored, vec![change(ChangeKind::Modified, "a.py".to_string(), false)]);
+        assert_eq!(moved_in, vec![change(ChangeKind::Created, "c.py".to_string(), false)]);
+        assert_eq!(
+            safe_write,
+            vec![
+                change(ChangeKind::Deleted, "b.py.tmp".to_string(), false),
+                change(ChangeKind::Modified, "b.py".to_string(), false),
+            ]
+        );
+        assert!(existing.contains("new.py") && !existing.contains("old.py") && !existing.contains("pkg/mod.py"));
+        assert_eq!(removed, vec![change(ChangeKind::Deleted, "pkg".to_string(), true)]);
+        assert_eq!(removed_any, removed);
+        assert_eq!(removed_file, vec![change(ChangeKind::Deleted, "a.py".to_string(), false)]);
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                change(ChangeKind::Created, "x.py".to_string(), false),
+                change(ChangeKind::Modified, "y.py".to_string(), false),
+            ]
+        );
+    }
+}
@@ -6,6 +6,15 @@ import { open } from '@tauri-apps/plugin-dialog'
  * 扫描项目列表
  */
 export async function scanProjects() {
+  const result = await invoke('scan_projects')
+  return result.projects
+}
+
+/**
+ * 扫描项目列表，同时返回需要修复的项目
+ * @returns {Promise<{projects: Array, needs_repair: Array}>}
+ */
+export async function scanProjectsWithIssues() {
   return invoke('scan_projects')
 }
 
@@ -16,7 +25,8 @@ export async function createProject(params) {
   return invoke('create_project', {
     name: params.name,
     description: params.description,
-    rootPath: params.rootPath
+    rootPath: params.rootPath,
+    templateId: params.templateId
   })
 }
 
@@ -55,6 +65,14 @@ export async function getProjectFiles(projectId) {
   return invoke('get_project_files', { projectId })
 }
 
+/**
+ * 分页列出项目中的一个目录，展开文件夹时按需加载
+ * 返回 { path, entries, total, has_more, next_cursor }，has_more 为 true 时用 next_cursor 读取下一页
+ */
+export async function listDirectory(projectId, path = '', cursor = null, limit = null) {
+  return invoke('list_directory', { projectId, path, cursor, limit })
+}
+
 /**
  * 获取源文件内容
  */
@@ -140,6 +158,558 @@ export async function moveFile(projectId, source, target) {
   })
 }
 
+// ===== 代码运行 API =====
+
+/**
+ * 运行项目入口或指定文件（返回 run_id，输出通过 run-output / run-exited 事件推送）
+ */
+export async function runProject(projectId, relativePath = null, timeoutSecs = null) {
+  return invoke('run_project', {
+    projectId,
+    relativePath,
+    timeoutSecs
+  })
+}
+
+/**
+ * 向运行中的程序写入标准输入
+ */
+export async function writeRunStdin(runId, input, close = false) {
+  return invoke('write_run_stdin', {
+    runId,
+    input,
+    close
+  })
+}
+
+/**
+ * 终止运行中的程序
+ */
+export async function killRun(runId) {
+  return invoke('kill_run', { runId })
+}
+
+/**
+ * 列出正在运行的程序
+ */
+export async function listRuns() {
+  return invoke('list_runs')
+}
+
+/**
+ * 解释运行失败的原因（runId 为空时使用项目最近一次运行，也可以直接传入错误输出）
+ */
+export async function explainRunError(projectId, runId = null, output = null) {
+  return invoke('explain_run_error', {
+    projectId,
+    runId,
+    output
+  })
+}
+
+// ===== 测试 API =====
+
+/**
+ * 为源文件生成单元测试并运行
+ */
+export async function generateTests(projectId, relativePath) {
+  return invoke('generate_tests', {
+    projectId,
+    relativePath
+  })
+}
+
+/**
+ * 根据需求文档中的验收标准生成 Gherkin 场景和测试骨架并运行
+ */
+export async function generateAcceptanceTests(projectId) {
+  return invoke('generate_acceptance_tests', { projectId })
+}
+
+/**
+ * 运行项目测试（testFile 为空时运行全部测试）
+ */
+export async function runProjectTests(projectId, testFile = null) {
+  return invoke('run_project_tests', {
+    projectId,
+    testFile
+  })
+}
+
+// ===== 项目语言 API =====
+
+/**
+ * 重新扫描项目代码，更新主语言和语言占比
+ */
+export async function rescanProjectLanguages(projectId) {
+  return invoke('rescan_project_languages', { projectId })
+}
+
+// ===== 项目模板 API =====
+
+/**
+ * 列出项目模板（指定语言时该语言的模板排在前面）
+ */
+export async function listTemplates(language = null) {
+  return invoke('list_templates', { language })
+}
+
+/**
+ * 将项目保存为模板
+ */
+export async function saveProjectAsTemplate(projectId, name, description) {
+  return invoke('save_project_as_template', {
+    projectId,
+    name,
+    description
+  })
+}
+
+/**
+ * 删除用户模板
+ */
+export async function deleteTemplate(templateId) {
+  return invoke('delete_template', { templateId })
+}
+
+// ===== 导入已有代码 API =====
+
+/**
+ * 导入已有代码目录（扫描代码并生成架构概览和初始需求文档）
+ */
+export async function importProject(rootPath, name = null, description = null) {
+  return invoke('import_project', {
+    rootPath,
+    name,
+    description
+  })
+}
+
+/**
+ * 为已关联目录的项目重新生成架构概览和需求文档
+ */
+export async function onboardProject(projectId) {
+  return invoke('onboard_project', { projectId })
+}
+
+// ===== 项目文档 API =====
+
+/**
+ * 列出项目文档（架构概览、模块参考、README）及其是否过期
+ * @param {string} projectId - 项目ID
+ */
+export async function listProjectDocs(projectId) {
+  return await invoke('list_project_docs', { projectId })
+}
+
+/**
+ * 根据当前代码生成或刷新文档
+ * @param {string} projectId - 项目ID
+ * @param {string} kind - 文档类型：architecture / reference / readme
+ */
+export async function generateProjectDoc(projectId, kind) {
+  return await invoke('generate_project_doc', { projectId, kind })
+}
+
+// ===== 项目导出导入 API =====
+
+/**
+ * 导出项目为 zip 文件
+ * @param {string} projectId - 项目ID
+ * @param {string} targetPath - 导出文件路径
+ * @param {boolean} includeSource - 是否包含源码（默认包含）
+ */
+export async function exportProject(projectId, targetPath, includeSource = true) {
+  return await invoke('export_project', { projectId, targetPath, includeSource })
+}
+
+/**
+ * 从 zip 文件导入项目
+ * @param {string} archivePath - 归档文件路径
+ * @param {string|null} targetRoot - 源码存放目录，为空时保存在应用内
+ */
+export async function importProjectArchive(archivePath, targetRoot = null) {
+  return await invoke('import_project_archive', { archivePath, targetRoot })
+}
+
+// ===== 项目修复 API =====
+
+/**
+ * 根据目录内容重建损坏项目的元数据
+ * @param {string} projectId - 项目ID（目录名）
+ */
+export async function repairProject(projectId) {
+  return await invoke('repair_project', { projectId })
+}
+
+/**
+ * 把损坏的项目移入隔离目录
+ * @param {string} projectId - 项目ID（目录名）
+ */
+export async function quarantineProject(projectId) {
+  return await invoke('quarantine_project', { projectId })
+}
+
+// ===== 项目整理 API =====
+
+/**
+ * 按条件筛选和排序项目
+ * @param {Object} query - 查询条件
+ * @param {string} query.text - 在名称和描述中搜索
+ * @param {string[]} query.tags - 必须包含的标签
+ * @param {string} query.language - 语言
+ * @param {string} query.archive - 归档状态：active / archived / all
+ * @param {string} query.sort - 排序方式：activity / name / created / language
+ * @param {boolean} query.reverse - 是否反转排序
+ */
+export async function queryProjects(query = {}) {
+  return await invoke('query_projects', { query })
+}
+
+/**
+ * 获取所有项目使用过的标签
+ */
+export async function listProjectTags() {
+  return await invoke('list_project_tags')
+}
+
+/**
+ * 更新项目的标签、置顶和归档状态，未传的字段保持不变
+ * @param {string} projectId - 项目ID
+ * @param {Object} flags - { tags, pinned, archived }
+ */
+export async function updateProjectFlags(projectId, { tags = null, pinned = null, archived = null } = {}) {
+  return await invoke('update_project_flags', { projectId, tags, pinned, archived })
+}
+
+// ===== 项目复制 API =====
+
+/**
+ * 复制项目为新项目
+ * @param {string} projectId - 来源项目ID
+ * @param {Object} options - 选项
+ * @param {string} options.name - 新项目名称，默认为「原名称 副本」
+ * @param {boolean} options.resetHistory - 是否清空对话和任务
+ * @param {string} options.targetRoot - 源码存放目录，为空时保存在应用内
+ */
+export async function duplicateProject(projectId, { name = null, resetHistory = false, targetRoot = null } = {}) {
+  return await invoke('duplicate_project', { projectId, name, resetHistory, targetRoot })
+}
+
+// ===== 重新关联目录 API =====
+
+/**
+ * 检查新目录是否与项目原来关联的目录为同一份代码
+ * @param {string} projectId - 项目ID
+ * @param {string} newRoot - 新目录路径
+ */
+export async function checkRelink(projectId, newRoot) {
+  return await invoke('check_relink', { projectId, newRoot })
+}
+
+/**
+ * 把项目重新关联到新目录
+ * @param {string} projectId - 项目ID
+ * @param {string} newRoot - 新目录路径
+ * @param {boolean} force - 文件差异较大时是否仍然关联
+ */
+export async function relinkProject(projectId, newRoot, force = false) {
+  return await invoke('relink_project', { projectId, newRoot, force })
+}
+
+// ===== 需求文档版本 API =====
+
+/**
+ * 获取需求文档的版本列表（最新的在前）
+ * @param {string} projectId - 项目ID
+ */
+export async function listRequirementRevisions(projectId) {
+  return await invoke('list_requirement_revisions', { projectId })
+}
+
+/**
+ * 读取某个版本的完整内容
+ * @param {string} projectId - 项目ID
+ * @param {string} revisionId - 版本ID
+ */
+export async function getRequirementRevision(projectId, revisionId) {
+  return await invoke('get_requirement_revision', { projectId, revisionId })
+}
+
+/**
+ * 对比两个版本，to 为空时与当前文档对比
+ * @param {string} projectId - 项目ID
+ * @param {string} from - 旧版本ID
+ * @param {string|null} to - 新版本ID
+ */
+export async function diffRequirementRevisions(projectId, from, to = null) {
+  return await invoke('diff_requirement_revisions', { projectId, from, to })
+}
+
+/**
+ * 把需求文档恢复到某个版本
+ * @param {string} projectId - 项目ID
+ * @param {string} revisionId - 版本ID
+ */
+export async function restoreRequirementRevision(projectId, revisionId) {
+  return await invoke('restore_requirement_revision', { projectId, revisionId })
+}
+
+// ===== 需求条目 API =====
+
+/**
+ * 读取需求文档的结构化模型
+ * @param {string} projectId - 项目ID
+ * @returns {Promise<{title, description, functional, non_functional, tech_stack, acceptance, open_questions, sections}>}
+ */
+export async function getRequirementModel(projectId) {
+  return await invoke('get_requirement_model', { projectId })
+}
+
+/**
+ * 添加一条需求
+ * @param {string} projectId - 项目ID
+ * @param {string} section - 章节：functional / non_functional / tech_stack / acceptance / open_questions
+ * @param {string} text - 需求内容
+ * @param {string|null} after - 插入到该条目之后，为空时添加到章节末尾
+ */
+export async function addRequirementItem(projectId, section, text, after = null) {
+  return await invoke('add_requirement_item', { projectId, section, text, after })
+}
+
+/**
+ * 修改一条需求的内容
+ * @param {string} projectId - 项目ID
+ * @param {string} itemId - 需求ID，例如 FR-1
+ * @param {string} text - 新内容
+ */
+export async function updateRequirementItem(projectId, itemId, text) {
+  return await invoke('update_requirement_item', { projectId, itemId, text })
+}
+
+/**
+ * 删除一条需求
+ * @param {string} projectId - 项目ID
+ * @param {string} itemId - 需求ID
+ */
+export async function removeRequirementItem(projectId, itemId) {
+  return await invoke('remove_requirement_item', { projectId, itemId })
+}
+
+/**
+ * 移动一条需求到所在章节的指定位置
+ * @param {string} projectId - 项目ID
+ * @param {string} itemId - 需求ID
+ * @param {number} index - 目标位置（从 0 开始）
+ */
+export async function moveRequirementItem(projectId, itemId, index) {
+  return await invoke('move_requirement_item', { projectId, itemId, index })
+}
+
+// ===== 需求追踪 API =====
+
+/**
+ * 获取全部需求追踪关联
+ * @param {string} projectId - 项目ID
+ */
+export async function listTraceLinks(projectId) {
+  return await invoke('list_trace_links', { projectId })
+}
+
+/**
+ * 手动把需求关联到文件和任务
+ * @param {string} projectId - 项目ID
+ * @param {string} requirementId - 需求ID，例如 FR-1
+ * @param {string[]} files - 源文件相对路径
+ * @param {string[]} tasks - 任务ID
+ */
+export async function linkRequirement(projectId, requirementId, files = [], tasks = []) {
+  return await invoke('link_requirement', { projectId, requirementId, files, tasks })
+}
+
+/**
+ * 删除一条需求追踪关联
+ * @param {string} projectId - 项目ID
+ * @param {string} requirementId - 需求ID
+ * @param {string} kind - 关联类型：file / task
+ * @param {string} target - 文件路径或任务ID
+ */
+export async function unlinkRequirement(projectId, requirementId, kind, target) {
+  return await invoke('unlink_requirement', { projectId, requirementId, kind, target })
+}
+
+/**
+ * 生成需求覆盖报告
+ * @param {string} projectId - 项目ID
+ */
+export async function getCoverageReport(projectId) {
+  return await invoke('get_coverage_report', { projectId })
+}
+
+// ===== 需求差距分析 API =====
+
+/**
+ * 对比需求文档和代码，分析每条需求的实现情况
+ * @param {string} projectId - 项目ID
+ * @param {number|null} tokenBudget - 代码摘要的 token 预算，为空时使用默认值
+ */
+export async function analyzeRequirementGaps(projectId, tokenBudget = null) {
+  return await invoke('analyze_requirement_gaps', { projectId, tokenBudget })
+}
+
+/**
+ * 获取最近一次的需求差距分析结果（没有时返回 null）
+ * @param {string} projectId - 项目ID
+ */
+export async function getGapReport(projectId) {
+  return await invoke('get_gap_report', { projectId })
+}
+
+// ===== 需求访谈 API =====
+
+/**
+ * 获取项目的需求访谈（没有时返回 null）
+ * @param {string} projectId - 项目ID
+ */
+export async function getInterview(projectId) {
+  return await invoke('get_interview', { projectId })
+}
+
+/**
+ * 开始或继续需求访谈
+ * @param {string} projectId - 项目ID
+ * @param {string} idea - 最初的一句话想法
+ * @param {boolean} restart - 是否放弃未完成的访谈重新开始
+ */
+export async function startInterview(projectId, idea, restart = false) {
+  return await invoke('start_interview', { projectId, idea, restart })
+}
+
+/**
+ * 回答当前问题，返回包含下一个问题的访谈
+ * @param {string} projectId - 项目ID
+ * @param {string} answer - 回答内容
+ */
+export async function answerInterview(projectId, answer) {
+  return await invoke('answer_interview', { projectId, answer })
+}
+
+/**
+ * 根据访谈记录生成需求文档
+ * @param {string} projectId - 项目ID
+ */
+export async function finishInterview(projectId) {
+  return await invoke('finish_interview', { projectId })
+}
+
+/**
+ * 放弃进行中的需求访谈
+ * @param {string} projectId - 项目ID
+ */
+export async function cancelInterview(projectId) {
+  return await invoke('cancel_interview', { projectId })
+}
+
+// ===== 项目搜索与替换 API =====
+
+/**
+ * 在项目目录中搜索，返回 searchId；结果通过 search-result 事件逐个文件推送，结束时推送 search-finished 事件
+ * @param {string} projectId - 项目ID
+ * @param {Object} options - 搜索条件
+ * @param {string} options.query - 搜索内容
+ * @param {boolean} options.regex - 是否按正则表达式搜索
+ * @param {boolean} options.caseSensitive - 是否区分大小写
+ * @param {string[]} options.include - 只搜索匹配这些 glob 的文件，例如 *.py
+ * @param {string[]} options.exclude - 排除匹配这些 glob 的文件
+ * @param {number} options.maxResults - 最多返回的匹配行数
+ * @param {number} options.contextLines - 每个匹配前后显示的行数
+ */
+export async function searchProject(projectId, options) {
+  return await invoke('search_project', {
+    projectId,
+    options: {
+      query: options.query,
+      regex: options.regex ?? false,
+      case_sensitive: options.caseSensitive ?? false,
+      include: options.include ?? [],
+      exclude: options.exclude ?? [],
+      max_results: options.maxResults ?? null,
+      context_lines: options.contextLines ?? null
+    }
+  })
+}
+
+/**
+ * 取消进行中的搜索
+ * @param {string} searchId - 搜索ID
+ */
+export async function cancelSearch(searchId) {
+  return await invoke('cancel_search', { searchId })
+}
+
+/**
+ * 预览项目范围的替换，返回每个文件的 diff 和匹配列表
+ * @param {string} projectId - 项目ID
+ * @param {Object} options - 与 searchProject 相同的搜索条件，另加 replacement（正则模式下可用 $1 引用捕获组）
+ */
+export async function previewReplace(projectId, options) {
+  return await invoke('preview_replace', { projectId, options: replaceOptions(options) })
+}
+
+/**
+ * 应用替换，有文件在预览后被修改时不写入任何文件，并在 conflicts 中列出这些文件；符号链接不替换，列在 skipped 中
+ * @param {string} projectId - 项目ID
+ * @param {Object} options - 与预览时相同的替换条件
+ * @param {Array<{path: string, hash: string, edits: number[]|null}>} selections - 选择的文件和匹配序号（edits 为 null 时替换全部）
+ */
+export async function applyReplace(projectId, options, selections) {
+  return await invoke('apply_replace', { projectId, options: replaceOptions(options), selections })
+}
+
+function replaceOptions(options) {
+  return {
+    query: options.query,
+    regex: options.regex ?? false,
+    case_sensitive: options.caseSensitive ?? false,
+    include: options.include ?? [],
+    exclude: options.exclude ?? [],
+    replacement: options.replacement ?? ''
+  }
+}
+
+// ===== 代码符号 API =====
+
+/**
+ * 读取文件大纲（函数、类和方法及其行号范围）
+ */
+export async function getFileOutline(projectId, relativePath) {
+  return await invoke('get_file_outline', { projectId, relativePath })
+}
+
+/**
+ * 按名称查找项目中的符号，用于跳转到符号
+ */
+export async function searchSymbols(projectId, query, limit = null) {
+  return await invoke('search_symbols', { projectId, query, limit })
+}
+
+// ===== 文件监听 API =====
+
+/**
+ * 打开项目时开始监听项目目录
+ * 修改通过 project-files-changed 事件推送：{ project_id, changes: [{ kind, path, from, is_dir }] }
+ */
+export async function watchProject(projectId) {
+  return await invoke('watch_project', { projectId })
+}
+
+/**
+ * 关闭项目时停止监听
+ */
+export async function unwatchProject(projectId) {
+  return await invoke('unwatch_project', { projectId })
+}
+
 // ===== OpenCode API =====
 
 /**
@@ -172,6 +742,13 @@ export async function createFilesWithAgentAsync(projectId, userInput) {
   })
 }
 
+/**
+ * 异步 Agent 任务结束后调用，由后端处理 Agent 修改过的文件
+ */
+export async function completeAgentTask(sessionId) {
+  return invoke('complete_agent_task', { sessionId })
+}
+
 /**
  * 获取会话消息列表（用于轮询）
  */